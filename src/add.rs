@@ -0,0 +1,214 @@
+//! Scaffolding of new data.yml items from tracking issues.
+
+use crate::data::edit::{self, Document};
+use crate::data::input::{slugify, InputData};
+use crate::data::IssueId;
+use crate::query::{GitHubQuery, TrackingIssue};
+use crate::{DATA_FILE, RUSTC_REPO};
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::fs;
+
+static RE_TITLE_PREFIX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)^tracking issue\b[:\s]*(for\s+)?(rfc #?\d+\s*[:,-]?\s*)?").unwrap()
+});
+static RE_RFC_TEXT: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"rust-lang/rfcs/blob/[^/\s]+/text/(\d{4}-[^/#.\s)]+)\.md").unwrap());
+static RE_RFC_PR: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"rust-lang/rfcs/(?:pull|issues)/(\d+)").unwrap());
+static RE_RFC_MENTION: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)\bRFC[ #]*(\d+)\b").unwrap());
+
+pub async fn run(query: &GitHubQuery<'_>, tracking: IssueId, section: &str) -> Result<()> {
+    let text = fs::read_to_string(DATA_FILE).context("failed to read data.yml")?;
+    let input_data = InputData::parse(&text).context("failed to read input data")?;
+    if !input_data.0.contains_key(section) {
+        bail!("unknown section `{}`", section);
+    }
+    if let Some(item) = input_data
+        .all_items()
+        .into_iter()
+        .find(|item| item.tracking == Some(tracking))
+    {
+        bail!(
+            "tracking issue #{} is already used by `{}`",
+            tracking,
+            item.id()
+        );
+    }
+
+    let issue = query.query_tracking_issue(&RUSTC_REPO, tracking).await?;
+    let (id, lines) = item_lines(&issue);
+    if input_data.ids().contains(&id) {
+        bail!("item `{}` already exists", id);
+    }
+
+    let mut document = Document::new(&text);
+    document.append_item(section, &lines)?;
+    let new_text = document.text();
+    InputData::parse(&new_text).context("added item is invalid")?;
+    fs::write(DATA_FILE, new_text).context("failed to write data.yml")?;
+    eprintln!("added `{}` to {}", id, section);
+    Ok(())
+}
+
+/// Build the YAML lines of a new item for the tracking issue, along with
+/// the id of the item.
+pub fn item_lines(issue: &TrackingIssue) -> (String, Vec<String>) {
+    let title = capitalize(&RE_TITLE_PREFIX.replace(issue.title.trim(), ""));
+    let mut lines = vec![format!("title: {}", edit::quote(&title))];
+    if let Some(rfc) = find_rfc(&issue.title, &issue.body) {
+        lines.push(format!("rfc: {}", edit::quote(&rfc)));
+    }
+    lines.push(format!("tracking: {}", issue.number));
+    if let Some(label) = find_label(&issue.labels) {
+        lines.push(format!("issue_label: {}", label));
+    }
+    (slugify(&title), lines)
+}
+
+/// Find the RFC of the tracking issue, preferring links to the merged text
+/// over links to the pull request and plain mentions.
+fn find_rfc(title: &str, body: &str) -> Option<String> {
+    if let Some(caps) = RE_RFC_TEXT.captures(body) {
+        return Some(caps[1].to_string());
+    }
+    let caps = RE_RFC_PR
+        .captures(body)
+        .or_else(|| RE_RFC_MENTION.captures(title))
+        .or_else(|| RE_RFC_MENTION.captures(body))?;
+    Some(caps[1].to_string())
+}
+
+/// Pick the feature label, or the area label if there is no feature label.
+fn find_label(labels: &[String]) -> Option<&str> {
+    let find = |prefix: &str| labels.iter().find(|label| label.starts_with(prefix));
+    find("F-").or_else(|| find("A-")).map(String::as_str)
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn issue(title: &str, body: &str, labels: &[&str]) -> TrackingIssue {
+        TrackingIssue {
+            number: 62290,
+            title: title.to_string(),
+            body: body.to_string(),
+            created_at: Utc.timestamp_opt(0, 0).unwrap(),
+            labels: labels.iter().map(|label| label.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn item_from_tracking_issue() {
+        let issue = issue(
+            "Tracking issue for RFC 2394, async_await",
+            "This is a tracking issue for the RFC \"async/await\" \
+             (rust-lang/rfcs#2394).\n\n\
+             See https://github.com/rust-lang/rfcs/blob/master/text/2394-async_await.md",
+            &[
+                "B-RFC-approved",
+                "A-async-await",
+                "F-async_await",
+                "C-tracking-issue",
+            ],
+        );
+        let (id, lines) = item_lines(&issue);
+        assert_eq!(id, "async-await");
+        assert_eq!(
+            lines,
+            [
+                "title: \"Async_await\"",
+                "rfc: \"2394-async_await\"",
+                "tracking: 62290",
+                "issue_label: F-async_await",
+            ]
+        );
+    }
+
+    #[test]
+    fn item_without_rfc_and_label() {
+        let issue = issue(
+            "`async` closures",
+            "Nothing linked here.",
+            &["C-tracking-issue"],
+        );
+        let (id, lines) = item_lines(&issue);
+        assert_eq!(id, "async-closures");
+        assert_eq!(lines, ["title: \"`async` closures\"", "tracking: 62290"]);
+    }
+
+    #[test]
+    fn strip_title_prefix() {
+        let strip = |title: &str| RE_TITLE_PREFIX.replace(title, "").into_owned();
+        assert_eq!(
+            strip("Tracking issue for async fn in traits"),
+            "async fn in traits"
+        );
+        assert_eq!(
+            strip("tracking issue for RFC #2394: async/await"),
+            "async/await"
+        );
+        assert_eq!(
+            strip("Tracking Issue for RFC 3185 - static async fn"),
+            "static async fn"
+        );
+        assert_eq!(strip("Tracking issue: Pin APIs"), "Pin APIs");
+        assert_eq!(strip("Async iterators"), "Async iterators");
+    }
+
+    #[test]
+    fn rfc_from_text_link() {
+        let body = "RFC: rust-lang/rfcs#2394\n\
+                    https://github.com/rust-lang/rfcs/blob/master/text/2394-async_await.md#guide";
+        assert_eq!(find_rfc("", body).as_deref(), Some("2394-async_await"));
+    }
+
+    #[test]
+    fn rfc_from_pull_request_link() {
+        let body = "Implements RFC 1234, see https://github.com/rust-lang/rfcs/pull/2394";
+        assert_eq!(find_rfc("", body).as_deref(), Some("2394"));
+    }
+
+    #[test]
+    fn rfc_from_mention() {
+        assert_eq!(
+            find_rfc("Tracking issue for RFC #3185", "").as_deref(),
+            Some("3185")
+        );
+        // Mentions in the title take priority over ones in the body.
+        assert_eq!(
+            find_rfc("RFC 3185", "Follows RFC 2394").as_deref(),
+            Some("3185")
+        );
+        assert_eq!(
+            find_rfc("Tracking issue", "Follows rfc 2394.").as_deref(),
+            Some("2394")
+        );
+        assert_eq!(find_rfc("Tracking issue", "RFCs are not linked."), None);
+    }
+
+    #[test]
+    fn feature_label_over_area_label() {
+        let labels = |labels: &[&str]| labels.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            find_label(&labels(&["A-async-await", "F-async_closure"])),
+            Some("F-async_closure")
+        );
+        assert_eq!(
+            find_label(&labels(&["C-tracking-issue", "A-async-await"])),
+            Some("A-async-await")
+        );
+        assert_eq!(find_label(&labels(&["C-tracking-issue", "T-lang"])), None);
+    }
+}
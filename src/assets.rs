@@ -0,0 +1,91 @@
+//! Templates and static files compiled into the binary.
+//!
+//! Files in override directories shadow the built-in files with the same
+//! relative path, so the site can be themed without modifying the source.
+
+use anyhow::{anyhow, Context, Result};
+use include_dir::{include_dir, Dir};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+static TEMPLATES: Dir = include_dir!("$CARGO_MANIFEST_DIR/templates");
+static STATIC: Dir = include_dir!("$CARGO_MANIFEST_DIR/static");
+
+pub const CNAME: &[u8] = include_bytes!("../CNAME");
+
+/// Collect all templates, keyed by their names.
+pub fn templates(override_dir: Option<&Path>) -> Result<BTreeMap<String, String>> {
+    let mut result = BTreeMap::new();
+    for (path, content) in embedded_files(&TEMPLATES) {
+        let content = std::str::from_utf8(content)
+            .map_err(|_| anyhow!("template {} is not UTF-8", path.display()))?;
+        result.insert(template_name(&path), content.to_string());
+    }
+    if let Some(dir) = override_dir {
+        for path in list_files(dir).context("failed to list override templates")? {
+            let content = fs::read_to_string(dir.join(&path))
+                .with_context(|| format!("failed to read template {}", path.display()))?;
+            result.insert(template_name(&path), content);
+        }
+    }
+    Ok(result)
+}
+
+/// Write all static files into the given directory.
+pub fn copy_static_files(dest: &Path, override_dir: Option<&Path>) -> io::Result<()> {
+    for (path, content) in embedded_files(&STATIC) {
+        write_file(&dest.join(path), content)?;
+    }
+    if let Some(dir) = override_dir {
+        for path in list_files(dir)? {
+            write_file(&dest.join(&path), &fs::read(dir.join(&path))?)?;
+        }
+    }
+    Ok(())
+}
+
+fn embedded_files(dir: &'static Dir<'static>) -> Vec<(PathBuf, &'static [u8])> {
+    let mut result: Vec<_> = dir
+        .files()
+        .map(|file| (file.path().to_path_buf(), file.contents()))
+        .collect();
+    for dir in dir.dirs() {
+        result.extend(embedded_files(dir));
+    }
+    result
+}
+
+/// List paths of all files under the given directory, relative to it.
+fn list_files(root: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut result = Vec::new();
+    let mut dirs = vec![PathBuf::new()];
+    while let Some(dir) = dirs.pop() {
+        for entry in root.join(&dir).read_dir()? {
+            let entry = entry?;
+            let path = dir.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                dirs.push(path);
+            } else {
+                result.push(path);
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn template_name(path: &Path) -> String {
+    let components: Vec<_> = path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect();
+    components.join("/")
+}
+
+fn write_file(path: &Path, content: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content)
+}
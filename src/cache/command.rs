@@ -0,0 +1,376 @@
+use super::key::{self, CacheKey};
+use super::{Backend, Cache};
+use crate::data::input::InputData;
+use crate::fetcher::IssueData;
+use crate::DATA_FILE;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use clap::Subcommand;
+use std::collections::BTreeSet;
+
+#[derive(Subcommand)]
+pub enum CacheCommand {
+    /// Copy the cache into another backend
+    Convert {
+        #[arg(value_enum)]
+        to: Backend,
+    },
+    /// Remove entries no longer referenced by data.yml
+    Gc {
+        /// Only print what would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Remove entries so that they are fetched again on the next build
+    Invalidate {
+        /// Keys like `rust-lang/rust#62290`, or labels like `A-async-await`
+        #[arg(required = true, value_name = "KEY")]
+        keys: Vec<CacheKey>,
+    },
+    /// List all entries with when they were fetched
+    List,
+    /// Upgrade the cache to the current format version
+    Migrate,
+    /// Show an entry and the items referencing it
+    Show {
+        /// Key like `rust-lang/rust#62290`, or label like `A-async-await`
+        key: CacheKey,
+    },
+    /// Print a summary of the cache contents
+    Stats,
+}
+
+pub fn run(command: CacheCommand) -> Result<()> {
+    let backend = Backend::from_env()?;
+    let mut cache = Cache::open_backend(backend)?;
+    match command {
+        CacheCommand::Convert { to } => convert(&mut cache, backend, to),
+        CacheCommand::Gc { dry_run } => gc(&mut cache, dry_run),
+        CacheCommand::Invalidate { keys } => invalidate(&mut cache, &keys),
+        CacheCommand::List => list(&mut cache),
+        CacheCommand::Migrate => migrate(&mut cache),
+        CacheCommand::Show { key } => show(&mut cache, &key),
+        CacheCommand::Stats => stats(&mut cache),
+    }
+}
+
+fn convert(cache: &mut Cache, from: Backend, to: Backend) -> Result<()> {
+    if from == to {
+        bail!("cache is already stored in this backend");
+    }
+    let (data, _) = cache.load()?;
+    let mut target = Cache::open_backend(to)?;
+    target.store(&data)?;
+    println!(
+        "copied {} labels and {} issues",
+        data.labels.len(),
+        data.issues.len()
+    );
+    Ok(())
+}
+
+fn gc(cache: &mut Cache, dry_run: bool) -> Result<()> {
+    let input_data = InputData::from_file(DATA_FILE).context("failed to read input data")?;
+    let (mut data, _) = cache.load()?;
+    let garbage = data.find_garbage(&input_data.get_fetch_list());
+    for (repo, label) in garbage.labels.iter() {
+        println!("label {} {}", repo, label);
+    }
+    for (repo, id) in garbage.issues.iter() {
+        println!("issue {}#{}", repo, id);
+    }
+    for (repo, id) in garbage.tracking.iter() {
+        println!("tracking {}#{}", repo, id);
+    }
+    let verb = if dry_run { "would remove" } else { "removed" };
+    println!(
+        "{} {} labels, {} issues and details of {} tracking issues",
+        verb,
+        garbage.labels.len(),
+        garbage.issues.len(),
+        garbage.tracking.len()
+    );
+    if !dry_run && !garbage.is_empty() {
+        data.remove_garbage(&garbage);
+        cache.store(&data)?;
+    }
+    Ok(())
+}
+
+fn invalidate(cache: &mut Cache, keys: &[CacheKey]) -> Result<()> {
+    let (mut data, _) = cache.load()?;
+    let mut count = 0;
+    for key in keys {
+        let removed = remove_entry(&mut data, key);
+        if removed.is_empty() {
+            bail!("`{}` is not in the cache", key);
+        }
+        for removed in removed.iter().filter(|removed| *removed != key) {
+            println!("also invalidating label {}", removed);
+        }
+        count += removed.len();
+    }
+    cache.store(&data)?;
+    println!("invalidated {} entries", count);
+    Ok(())
+}
+
+/// Remove the entry of the given key, and return keys of all entries removed.
+///
+/// Issues listed under a label are only fetched with the label, so labels
+/// listing a removed issue are removed as well.
+fn remove_entry(data: &mut IssueData, key: &CacheKey) -> Vec<CacheKey> {
+    let mut removed = vec![];
+    match key {
+        CacheKey::Label(repo, label) => {
+            let key = (repo.clone(), label.clone());
+            data.labels_fetched.remove(&key);
+            if data.labels.remove(&key).is_some() {
+                removed.push(CacheKey::Label(key.0, key.1));
+            }
+        }
+        CacheKey::Issue(repo, number) => {
+            let labels: Vec<_> = data
+                .labels
+                .iter()
+                .filter(|((r, _), numbers)| r == repo && numbers.contains(number))
+                .map(|(key, _)| key.clone())
+                .collect();
+            let key = (repo.clone(), *number);
+            data.bodies.remove(&key);
+            data.bodies_fetched.remove(&key);
+            data.linked_prs.remove(&key);
+            data.linked_prs_fetched.remove(&key);
+            data.issues_fetched.remove(&key);
+            if data.issues.remove(&key).is_some() {
+                removed.push(CacheKey::Issue(key.0, key.1));
+            }
+            for key in labels {
+                data.labels_fetched.remove(&key);
+                data.labels.remove(&key);
+                removed.push(CacheKey::Label(key.0, key.1));
+            }
+        }
+    }
+    removed
+}
+
+fn list(cache: &mut Cache) -> Result<()> {
+    let input_data = InputData::from_file(DATA_FILE).context("failed to read input data")?;
+    let (data, _) = cache.load()?;
+    let references = key::references(&input_data, &data);
+    let entries = data
+        .labels
+        .keys()
+        .map(|(repo, label)| {
+            let fetched = data.labels_fetched.get(&(repo.clone(), label.clone()));
+            (CacheKey::Label(repo.clone(), label.clone()), fetched)
+        })
+        .chain(data.issues.keys().map(|(repo, number)| {
+            let fetched = data.issues_fetched.get(&(repo.clone(), *number));
+            (CacheKey::Issue(repo.clone(), *number), fetched)
+        }));
+    for (key, fetched) in entries {
+        let items = references.get(&key).map_or(String::new(), join_ids);
+        println!("{}\t{}\t{}", key, format_time(fetched), items);
+    }
+    Ok(())
+}
+
+fn show(cache: &mut Cache, key: &CacheKey) -> Result<()> {
+    let input_data = InputData::from_file(DATA_FILE).context("failed to read input data")?;
+    let (data, _) = cache.load()?;
+    match key {
+        CacheKey::Label(repo, label) => {
+            let entry = (repo.clone(), label.clone());
+            let issues = match data.labels.get(&entry) {
+                Some(issues) => issues,
+                None => bail!("`{}` is not in the cache", key),
+            };
+            println!("{}", key);
+            println!("fetched: {}", format_time(data.labels_fetched.get(&entry)));
+            println!("issues:");
+            for number in issues {
+                match data.issues.get(&(repo.clone(), *number)) {
+                    Some(issue) => {
+                        let state = if issue.open { "open" } else { "closed" };
+                        println!("  #{} [{}] {}", number, state, issue.title);
+                    }
+                    None => println!("  #{} (missing)", number),
+                }
+            }
+        }
+        CacheKey::Issue(repo, number) => {
+            let entry = (repo.clone(), *number);
+            let issue = match data.issues.get(&entry) {
+                Some(issue) => issue,
+                None => bail!("`{}` is not in the cache", key),
+            };
+            println!("{}", key);
+            println!("title: {}", issue.title);
+            println!("state: {}", if issue.open { "open" } else { "closed" });
+            println!("fetched: {}", format_time(data.issues_fetched.get(&entry)));
+        }
+    }
+    let references = key::references(&input_data, &data);
+    match references.get(key) {
+        Some(ids) => println!("items: {}", join_ids(ids)),
+        None => println!("items: (none)"),
+    }
+    Ok(())
+}
+
+fn stats(cache: &mut Cache) -> Result<()> {
+    let input_data = InputData::from_file(DATA_FILE).context("failed to read input data")?;
+    let (data, version) = cache.load()?;
+    let open = data.issues.values().filter(|issue| issue.open).count();
+    let garbage = data.find_garbage(&input_data.get_fetch_list());
+    let times = data
+        .labels_fetched
+        .values()
+        .chain(data.issues_fetched.values())
+        .chain(data.bodies_fetched.values())
+        .chain(data.linked_prs_fetched.values());
+    let oldest = times.clone().min();
+    let newest = times.max();
+    println!("version: {}", version);
+    println!("labels: {}", data.labels.len());
+    println!(
+        "issues: {} ({} open, {} closed)",
+        data.issues.len(),
+        open,
+        data.issues.len() - open
+    );
+    println!("bodies: {}", data.bodies.len());
+    println!(
+        "linked pull requests: {} of {} tracking issues",
+        data.linked_prs.values().map(Vec::len).sum::<usize>(),
+        data.linked_prs.len()
+    );
+    println!(
+        "unreferenced: {} labels, {} issues, {} tracking issues",
+        garbage.labels.len(),
+        garbage.issues.len(),
+        garbage.tracking.len()
+    );
+    println!(
+        "fetch time unknown: {} labels, {} issues, {} bodies, {} linked pull requests",
+        data.labels.len() - data.labels_fetched.len(),
+        data.issues.len() - data.issues_fetched.len(),
+        data.bodies.len() - data.bodies_fetched.len(),
+        data.linked_prs.len() - data.linked_prs_fetched.len()
+    );
+    println!("oldest fetch: {}", format_time(oldest));
+    println!("newest fetch: {}", format_time(newest));
+    Ok(())
+}
+
+fn migrate(cache: &mut Cache) -> Result<()> {
+    let (data, version) = cache.load()?;
+    let current = cache.current_version();
+    if version == current {
+        println!("cache is already in version {}", version);
+        return Ok(());
+    }
+    cache.store(&data)?;
+    println!("migrated cache from version {} to {}", version, current);
+    Ok(())
+}
+
+fn format_time(time: Option<&DateTime<Utc>>) -> String {
+    match time {
+        Some(time) => time.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        None => "unknown".to_string(),
+    }
+}
+
+fn join_ids(ids: &BTreeSet<String>) -> String {
+    let ids: Vec<_> = ids.iter().map(String::as_str).collect();
+    ids.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Issue;
+    use crate::query::Repo;
+    use chrono::Utc;
+
+    fn issue(number: u32) -> Issue {
+        Issue {
+            number,
+            title: format!("issue {}", number),
+            open: true,
+            pull_request: None,
+        }
+    }
+
+    fn data() -> (Repo, IssueData) {
+        let repo = Repo::new("rust-lang", "rust");
+        let mut data = IssueData::default();
+        for number in [1, 2, 3] {
+            data.issues.insert((repo.clone(), number), issue(number));
+            data.issues_fetched
+                .insert((repo.clone(), number), Utc::now());
+        }
+        for (label, numbers) in [("A-a", vec![1, 2]), ("A-b", vec![3])] {
+            let key = (repo.clone(), label.to_string());
+            data.labels.insert(key.clone(), numbers);
+            data.labels_fetched.insert(key, Utc::now());
+        }
+        (repo, data)
+    }
+
+    #[test]
+    fn remove_issue_removes_labels_listing_it() {
+        let (repo, mut data) = data();
+        let removed = remove_entry(&mut data, &CacheKey::Issue(repo.clone(), 2));
+        assert_eq!(
+            removed,
+            vec![
+                CacheKey::Issue(repo.clone(), 2),
+                CacheKey::Label(repo.clone(), "A-a".to_string()),
+            ]
+        );
+        assert!(!data.issues.contains_key(&(repo.clone(), 2)));
+        assert!(!data.labels.contains_key(&(repo.clone(), "A-a".to_string())));
+        assert!(!data
+            .labels_fetched
+            .contains_key(&(repo.clone(), "A-a".to_string())));
+        assert!(data.labels.contains_key(&(repo.clone(), "A-b".to_string())));
+        assert!(data.issues.contains_key(&(repo, 1)));
+    }
+
+    #[test]
+    fn remove_issue_only_listed_under_label() {
+        let (repo, mut data) = data();
+        data.issues.remove(&(repo.clone(), 3));
+        let removed = remove_entry(&mut data, &CacheKey::Issue(repo.clone(), 3));
+        assert_eq!(removed, vec![CacheKey::Label(repo, "A-b".to_string())]);
+    }
+
+    #[test]
+    fn remove_missing_entry() {
+        let (repo, mut data) = data();
+        assert!(remove_entry(&mut data, &CacheKey::Issue(repo.clone(), 4)).is_empty());
+        assert!(remove_entry(&mut data, &CacheKey::Label(repo, "A-c".to_string())).is_empty());
+        assert_eq!(data.issues.len(), 3);
+        assert_eq!(data.labels.len(), 2);
+    }
+
+    #[test]
+    fn migrate_json_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.json");
+        let old = serde_json::json!({"version": 2, "labels": [], "issues": []});
+        std::fs::write(&path, old.to_string()).unwrap();
+        let mut cache = Cache::open_path(Backend::Json, &path).unwrap();
+        migrate(&mut cache).unwrap();
+        let stored: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(stored["version"], cache.current_version());
+        let (data, version) = cache.load().unwrap();
+        assert_eq!(version, cache.current_version());
+        assert_eq!(data, IssueData::default());
+    }
+}
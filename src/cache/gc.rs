@@ -0,0 +1,79 @@
+//! Removal of cache entries which are no longer referenced by data.yml.
+
+use crate::data::input::FetchList;
+use crate::data::IssueId;
+use crate::fetcher::IssueData;
+use crate::query::Repo;
+use std::collections::BTreeSet;
+
+/// Entries of the cache which are not reachable from a fetch list.
+#[derive(Default)]
+pub struct Garbage {
+    pub labels: Vec<(Repo, String)>,
+    pub issues: Vec<(Repo, IssueId)>,
+    /// Bodies and linked pull requests of issues no longer used as
+    /// tracking issues.
+    pub tracking: Vec<(Repo, IssueId)>,
+}
+
+impl Garbage {
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty() && self.issues.is_empty() && self.tracking.is_empty()
+    }
+}
+
+impl IssueData {
+    /// Find entries not referenced by the given fetch list, either directly
+    /// or via a referenced label.
+    pub fn find_garbage(&self, fetch_list: &FetchList<'_>) -> Garbage {
+        let live_labels: BTreeSet<_> = fetch_list
+            .labels
+            .iter()
+            .map(|(repo, label)| (repo, *label))
+            .collect();
+        let mut live_issues: BTreeSet<_> = fetch_list
+            .issues
+            .iter()
+            .map(|(repo, id)| (repo, *id))
+            .collect();
+        let mut garbage = Garbage::default();
+        for ((repo, label), issues) in self.labels.iter() {
+            if live_labels.contains(&(repo, label.as_str())) {
+                live_issues.extend(issues.iter().map(|id| (repo, *id)));
+            } else {
+                garbage.labels.push((repo.clone(), label.clone()));
+            }
+        }
+        garbage.issues = self
+            .issues
+            .keys()
+            .filter(|(repo, id)| !live_issues.contains(&(repo, *id)))
+            .cloned()
+            .collect();
+        let live_tracking: BTreeSet<_> = fetch_list.tracking.iter().collect();
+        let tracking: BTreeSet<_> = self.bodies.keys().chain(self.linked_prs.keys()).collect();
+        garbage.tracking = tracking
+            .into_iter()
+            .filter(|key| !live_tracking.contains(key))
+            .cloned()
+            .collect();
+        garbage
+    }
+
+    pub fn remove_garbage(&mut self, garbage: &Garbage) {
+        for key in garbage.labels.iter() {
+            self.labels.remove(key);
+            self.labels_fetched.remove(key);
+        }
+        for key in garbage.issues.iter() {
+            self.issues.remove(key);
+            self.issues_fetched.remove(key);
+        }
+        for key in garbage.tracking.iter() {
+            self.bodies.remove(key);
+            self.bodies_fetched.remove(key);
+            self.linked_prs.remove(key);
+            self.linked_prs_fetched.remove(key);
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! Storage of the cache in a single JSON file, rewritten on every store.
+
+use super::migrate::{self, CURRENT_VERSION};
+use super::{with_suffix, Storage};
+use crate::fetcher::IssueData;
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+pub struct JsonStorage {
+    path: PathBuf,
+}
+
+#[derive(Serialize)]
+struct VersionedData<'a> {
+    version: u32,
+    #[serde(flatten)]
+    data: &'a IssueData,
+}
+
+impl JsonStorage {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        JsonStorage { path: path.into() }
+    }
+}
+
+impl Storage for JsonStorage {
+    fn current_version(&self) -> u32 {
+        CURRENT_VERSION
+    }
+
+    fn load(&mut self) -> Result<Option<(IssueData, u32)>> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let value: Value = serde_json::from_reader(BufReader::new(file))?;
+        let (value, version) = migrate::migrate(value)?;
+        Ok(Some((serde_json::from_value(value)?, version)))
+    }
+
+    /// Store into the file atomically, via a temporary file which is
+    /// renamed to the target path when fully written.
+    fn store(&mut self, data: &IssueData) -> Result<()> {
+        let tmp_path = with_suffix(&self.path, ".tmp");
+        let file = File::create(&tmp_path)?;
+        let mut writer = BufWriter::new(file);
+        let data = VersionedData {
+            version: CURRENT_VERSION,
+            data,
+        };
+        serde_json::to_writer(&mut writer, &data)?;
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+        fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,78 @@
+//! Keys identifying entries in the cache.
+
+use crate::data::input::InputData;
+use crate::data::IssueId;
+use crate::fetcher::IssueData;
+use crate::query::Repo;
+use crate::RUSTC_REPO;
+use anyhow::{anyhow, Error};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+/// Key of a cache entry, written as `owner/name#number` for issues and
+/// `owner/name:label` for labels, where the repository defaults to
+/// rust-lang/rust for labels.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum CacheKey {
+    Label(Repo, String),
+    Issue(Repo, IssueId),
+}
+
+impl FromStr for CacheKey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((repo, number)) = s.split_once('#') {
+            let number = number
+                .parse()
+                .map_err(|_| anyhow!("invalid issue number in `{}`", s))?;
+            return Ok(CacheKey::Issue(parse_repo(repo)?, number));
+        }
+        match s.split_once(':') {
+            Some((repo, label)) if repo.contains('/') => {
+                Ok(CacheKey::Label(parse_repo(repo)?, label.to_string()))
+            }
+            _ => Ok(CacheKey::Label(RUSTC_REPO.clone(), s.to_string())),
+        }
+    }
+}
+
+fn parse_repo(s: &str) -> Result<Repo, Error> {
+    match s.split_once('/') {
+        Some((owner, name)) if !owner.is_empty() && !name.is_empty() => Ok(Repo::new(owner, name)),
+        _ => Err(anyhow!("invalid repository `{}`, expected `owner/name`", s)),
+    }
+}
+
+impl Display for CacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CacheKey::Label(repo, label) => write!(f, "{}:{}", repo, label),
+            CacheKey::Issue(repo, number) => write!(f, "{}#{}", repo, number),
+        }
+    }
+}
+
+/// Collect ids of items referencing each cache entry.
+///
+/// Issues listed under a label are referenced by items with that label.
+pub fn references(input: &InputData, data: &IssueData) -> BTreeMap<CacheKey, BTreeSet<String>> {
+    let mut result: BTreeMap<_, BTreeSet<_>> = BTreeMap::new();
+    for (id, fetch_list) in input.get_item_fetch_lists() {
+        for (repo, label) in fetch_list.labels {
+            let entry = (repo, label.to_string());
+            for number in data.labels.get(&entry).into_iter().flatten() {
+                let issue = CacheKey::Issue(entry.0.clone(), *number);
+                result.entry(issue).or_default().insert(id.clone());
+            }
+            let label = CacheKey::Label(entry.0, entry.1);
+            result.entry(label).or_default().insert(id.clone());
+        }
+        for (repo, number) in fetch_list.issues {
+            let issue = CacheKey::Issue(repo, number);
+            result.entry(issue).or_default().insert(id.clone());
+        }
+    }
+    result
+}
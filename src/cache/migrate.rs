@@ -0,0 +1,292 @@
+//! Migrations of the cache file format.
+//!
+//! Each migration converts the JSON of one version to the next version,
+//! so that existing cache files keep working when `Issue` evolves.
+//! Version 1 is the original format without the `version` field.
+
+use anyhow::{bail, Context, Result};
+use log::info;
+use serde_json::Value;
+
+pub const CURRENT_VERSION: u32 = 9;
+
+/// Migrations from each version to the next one, starting from version 1.
+const MIGRATIONS: [fn(Value) -> Result<Value>; CURRENT_VERSION as usize - 1] = [
+    v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5, v5_to_v6, v6_to_v7, v7_to_v8, v8_to_v9,
+];
+
+/// Migrate the given data to the current version.
+///
+/// Returns the migrated data and the version it was in.
+pub fn migrate(mut value: Value) -> Result<(Value, u32)> {
+    let original = match value.get("version") {
+        None => 1,
+        Some(version) => version
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .filter(|v| *v >= 1)
+            .context("invalid cache version")?,
+    };
+    if original > CURRENT_VERSION {
+        bail!(
+            "cache version {} is newer than the supported version {}",
+            original,
+            CURRENT_VERSION
+        );
+    }
+    for version in original..CURRENT_VERSION {
+        info!(
+            "migrating cache from version {} to {}...",
+            version,
+            version + 1
+        );
+        value = MIGRATIONS[version as usize - 1](value)
+            .with_context(|| format!("failed to migrate cache from version {}", version))?;
+        value["version"] = (version + 1).into();
+    }
+    Ok((value, original))
+}
+
+/// Version 2 only adds the `version` field.
+fn v1_to_v2(value: Value) -> Result<Value> {
+    Ok(value)
+}
+
+/// Version 3 records when each entry was fetched, which is unknown for
+/// existing entries.
+fn v2_to_v3(mut value: Value) -> Result<Value> {
+    value["labels_fetched"] = Value::Array(Vec::new());
+    value["issues_fetched"] = Value::Array(Vec::new());
+    Ok(value)
+}
+
+/// Version 4 records the merge state of pull requests, which is unknown for
+/// existing entries until they are fetched again.
+fn v3_to_v4(mut value: Value) -> Result<Value> {
+    let issues = value["issues"]
+        .as_array_mut()
+        .context("`issues` is not an array")?;
+    for entry in issues {
+        let issue = entry
+            .get_mut(1)
+            .and_then(Value::as_object_mut)
+            .context("invalid issue entry")?;
+        issue.insert("pull_request".to_string(), Value::Null);
+    }
+    Ok(value)
+}
+
+/// Version 5 stores bodies of tracking issues, which are fetched on the
+/// next run.
+fn v4_to_v5(mut value: Value) -> Result<Value> {
+    value["bodies"] = Value::Array(Vec::new());
+    Ok(value)
+}
+
+/// Version 6 stores pull requests linked to tracking issues, which are
+/// fetched on the next run.
+fn v5_to_v6(mut value: Value) -> Result<Value> {
+    value["linked_prs"] = Value::Array(Vec::new());
+    Ok(value)
+}
+
+/// Version 7 records when each body was fetched, which is unknown for
+/// existing bodies.
+fn v6_to_v7(mut value: Value) -> Result<Value> {
+    value["bodies_fetched"] = Value::Array(Vec::new());
+    Ok(value)
+}
+
+/// Version 8 records when linked pull requests were fetched, which is
+/// unknown for existing entries.
+fn v7_to_v8(mut value: Value) -> Result<Value> {
+    value["linked_prs_fetched"] = Value::Array(Vec::new());
+    Ok(value)
+}
+
+/// Version 9 records the latest release tag, which is unknown until the
+/// next build.
+fn v8_to_v9(mut value: Value) -> Result<Value> {
+    value["latest_tag"] = Value::Null;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Issue, LinkedPr, PrState, PullRequest};
+    use crate::fetcher::IssueData;
+    use crate::query::Repo;
+    use chrono::{DateTime, Utc};
+    use serde_json::json;
+
+    const TIME: &str = "2020-01-01T00:00:00Z";
+
+    /// Cache files as written by each version, oldest first.
+    fn fixtures() -> Vec<Value> {
+        let repo = json!({"owner": "rust-lang", "name": "rust"});
+        let label = json!([[repo, "A-a"], [1]]);
+        let issue = json!([[repo, 1], {"number": 1, "title": "Issue", "open": true}]);
+        let pr = json!([[repo, 1], {
+            "number": 1,
+            "title": "Issue",
+            "open": true,
+            "pull_request": {"merged": true, "milestone": "1.39"}
+        }]);
+        let label_time = json!([[repo, "A-a"], TIME]);
+        let issue_time = json!([[repo, 1], TIME]);
+        let body = json!([[repo, 1], "- [x] task"]);
+        let linked_prs = json!([[repo, 1], [{
+            "repo": repo,
+            "number": 2,
+            "title": "PR",
+            "state": "merged",
+            "merged_at": TIME
+        }]]);
+        vec![
+            json!({"labels": [label], "issues": [issue]}),
+            json!({"version": 2, "labels": [label], "issues": [issue]}),
+            json!({
+                "version": 3,
+                "labels": [label],
+                "issues": [issue],
+                "labels_fetched": [label_time],
+                "issues_fetched": [issue_time]
+            }),
+            json!({
+                "version": 4,
+                "labels": [label],
+                "issues": [pr],
+                "labels_fetched": [label_time],
+                "issues_fetched": [issue_time]
+            }),
+            json!({
+                "version": 5,
+                "labels": [label],
+                "issues": [pr],
+                "labels_fetched": [label_time],
+                "issues_fetched": [issue_time],
+                "bodies": [body]
+            }),
+            json!({
+                "version": 6,
+                "labels": [label],
+                "issues": [pr],
+                "labels_fetched": [label_time],
+                "issues_fetched": [issue_time],
+                "bodies": [body],
+                "linked_prs": [linked_prs]
+            }),
+            json!({
+                "version": 7,
+                "labels": [label],
+                "issues": [pr],
+                "labels_fetched": [label_time],
+                "issues_fetched": [issue_time],
+                "bodies": [body],
+                "bodies_fetched": [issue_time],
+                "linked_prs": [linked_prs]
+            }),
+            json!({
+                "version": 8,
+                "labels": [label],
+                "issues": [pr],
+                "labels_fetched": [label_time],
+                "issues_fetched": [issue_time],
+                "bodies": [body],
+                "bodies_fetched": [issue_time],
+                "linked_prs": [linked_prs],
+                "linked_prs_fetched": [issue_time]
+            }),
+            json!({
+                "version": 9,
+                "labels": [label],
+                "issues": [pr],
+                "labels_fetched": [label_time],
+                "issues_fetched": [issue_time],
+                "bodies": [body],
+                "bodies_fetched": [issue_time],
+                "linked_prs": [linked_prs],
+                "linked_prs_fetched": [issue_time],
+                "latest_tag": "1.40.0"
+            }),
+        ]
+    }
+
+    /// Data expected from the fixture of the given version, where anything
+    /// the version didn't store is unknown.
+    fn expected(version: u32) -> IssueData {
+        let repo = Repo::new("rust-lang", "rust");
+        let time: DateTime<Utc> = TIME.parse().unwrap();
+        let mut data = IssueData::default();
+        data.labels
+            .insert((repo.clone(), "A-a".to_string()), vec![1]);
+        let pull_request = Some(PullRequest {
+            merged: true,
+            milestone: Some("1.39".to_string()),
+        });
+        let issue = Issue {
+            number: 1,
+            title: "Issue".to_string(),
+            open: true,
+            pull_request: pull_request.filter(|_| version >= 4),
+        };
+        data.issues.insert((repo.clone(), 1), issue);
+        let key = (repo.clone(), 1);
+        if version >= 3 {
+            data.labels_fetched
+                .insert((repo.clone(), "A-a".to_string()), time);
+            data.issues_fetched.insert(key.clone(), time);
+        }
+        if version >= 5 {
+            data.bodies.insert(key.clone(), "- [x] task".to_string());
+        }
+        if version >= 6 {
+            let linked_pr = LinkedPr {
+                repo: repo.clone(),
+                number: 2,
+                title: "PR".to_string(),
+                state: PrState::Merged,
+                merged_at: Some(time),
+            };
+            data.linked_prs.insert(key.clone(), vec![linked_pr]);
+        }
+        if version >= 7 {
+            data.bodies_fetched.insert(key.clone(), time);
+        }
+        if version >= 8 {
+            data.linked_prs_fetched.insert(key, time);
+        }
+        if version >= 9 {
+            data.latest_tag = Some("1.40.0".to_string());
+        }
+        data
+    }
+
+    #[test]
+    fn fixture_of_every_version() {
+        let fixtures = fixtures();
+        assert_eq!(fixtures.len(), CURRENT_VERSION as usize);
+        for (version, fixture) in (1..).zip(fixtures) {
+            let (value, original) = migrate(fixture).unwrap();
+            assert_eq!(original, version);
+            assert_eq!(value["version"], CURRENT_VERSION);
+            let data: IssueData = serde_json::from_value(value).unwrap();
+            assert_eq!(data, expected(version), "version {}", version);
+        }
+    }
+
+    #[test]
+    fn newer_version_is_error() {
+        let value = json!({"version": CURRENT_VERSION + 1, "labels": [], "issues": []});
+        let error = migrate(value).unwrap_err();
+        assert!(error.to_string().contains("newer"), "{}", error);
+    }
+
+    #[test]
+    fn invalid_version_is_error() {
+        for version in [json!(0), json!(-1), json!("2")] {
+            assert!(migrate(json!({"version": version})).is_err());
+        }
+    }
+}
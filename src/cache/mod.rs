@@ -0,0 +1,180 @@
+//! Persistence of fetched data in the cache.
+
+use self::json::JsonStorage;
+use self::sqlite::SqliteStorage;
+use crate::fetcher::IssueData;
+use crate::{CACHE_DB, CACHE_FILE};
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use fs2::FileExt;
+use log::info;
+use std::env;
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+pub mod command;
+mod gc;
+mod json;
+mod key;
+mod migrate;
+mod sqlite;
+
+/// Backend storing the cached data.
+pub trait Storage {
+    /// Version of the format written by `store`.
+    fn current_version(&self) -> u32;
+    /// Load the stored data, migrated to the current version, along with
+    /// the version it was stored in, or `None` if nothing is stored yet.
+    fn load(&mut self) -> Result<Option<(IssueData, u32)>>;
+    fn store(&mut self, data: &IssueData) -> Result<()>;
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Backend {
+    /// Single JSON file, `cache.json`
+    Json,
+    /// SQLite database, `cache.sqlite`
+    Sqlite,
+}
+
+impl Backend {
+    /// Get the backend selected by the `CACHE_BACKEND` environment variable,
+    /// which defaults to JSON.
+    pub fn from_env() -> Result<Self> {
+        match env::var("CACHE_BACKEND") {
+            Ok(name) => match Backend::from_str(&name, true) {
+                Ok(backend) => Ok(backend),
+                Err(_) => bail!("unknown CACHE_BACKEND `{}`", name),
+            },
+            Err(env::VarError::NotPresent) => Ok(Backend::Json),
+            Err(e) => Err(e).context("invalid CACHE_BACKEND"),
+        }
+    }
+
+    fn path(self) -> &'static str {
+        match self {
+            Backend::Json => CACHE_FILE,
+            Backend::Sqlite => CACHE_DB,
+        }
+    }
+}
+
+/// Opened cache, locked until dropped.
+pub struct Cache {
+    path: PathBuf,
+    storage: Box<dyn Storage>,
+    _lock: CacheLock,
+}
+
+impl Cache {
+    /// Open the cache of the backend selected via environment variable.
+    pub fn open() -> Result<Self> {
+        Self::open_backend(Backend::from_env()?)
+    }
+
+    pub fn open_backend(backend: Backend) -> Result<Self> {
+        Self::open_path(backend, backend.path())
+    }
+
+    fn open_path(backend: Backend, path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let lock = CacheLock::acquire(&path)?;
+        let storage: Box<dyn Storage> = match backend {
+            Backend::Json => Box::new(JsonStorage::new(&path)),
+            Backend::Sqlite => Box::new(SqliteStorage::open(&path).with_context(|| {
+                format!("failed to open cache database {}", path.display())
+            })?),
+        };
+        Ok(Cache {
+            path,
+            storage,
+            _lock: lock,
+        })
+    }
+
+    pub fn current_version(&self) -> u32 {
+        self.storage.current_version()
+    }
+
+    /// Load the data and the version it was stored in, failing if the cache
+    /// is empty.
+    pub fn load(&mut self) -> Result<(IssueData, u32)> {
+        match self.load_inner()? {
+            Some(loaded) => Ok(loaded),
+            None => bail!("cache {} doesn't exist", self.path.display()),
+        }
+    }
+
+    /// Load the data, or return empty data if the cache is empty.
+    pub fn load_or_default(&mut self) -> Result<IssueData> {
+        Ok(self.load_inner()?.map(|(data, _)| data).unwrap_or_default())
+    }
+
+    fn load_inner(&mut self) -> Result<Option<(IssueData, u32)>> {
+        self.storage.load().with_context(|| {
+            format!(
+                "failed to read cache {}, remove it to fetch everything again",
+                self.path.display()
+            )
+        })
+    }
+
+    pub fn store(&mut self, data: &IssueData) -> Result<()> {
+        self.storage
+            .store(data)
+            .with_context(|| format!("failed to store to cache {}", self.path.display()))
+    }
+}
+
+/// Load the cache at the given path without locking or changing it, using
+/// the SQLite backend for `.sqlite` files and the JSON backend otherwise.
+pub fn load_path(path: &Path) -> Result<IssueData> {
+    if !path.exists() {
+        bail!("{} doesn't exist", path.display());
+    }
+    let mut storage: Box<dyn Storage> = match path.extension() {
+        Some(ext) if ext == "sqlite" => Box::new(SqliteStorage::open_read_only(path)?),
+        _ => Box::new(JsonStorage::new(path)),
+    };
+    match storage.load()? {
+        Some((data, _)) => Ok(data),
+        None => bail!("{} is empty", path.display()),
+    }
+}
+
+/// Exclusive advisory lock of the cache file, released when dropped.
+struct CacheLock(File);
+
+impl CacheLock {
+    /// Lock the given cache file, waiting for other processes holding it.
+    pub fn acquire(path: impl AsRef<Path>) -> Result<Self> {
+        let path = with_suffix(path.as_ref(), ".lock");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        if let Err(e) = file.try_lock_exclusive() {
+            if e.kind() != fs2::lock_contended_error().kind() {
+                return Err(e).context("failed to lock cache file");
+            }
+            info!("waiting for other process to release {}...", path.display());
+            file.lock_exclusive().context("failed to lock cache file")?;
+        }
+        Ok(CacheLock(file))
+    }
+}
+
+impl Drop for CacheLock {
+    fn drop(&mut self) {
+        let _ = self.0.unlock();
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(path);
+    path.push(suffix);
+    path.into()
+}
@@ -0,0 +1,769 @@
+//! Storage of the cache in an SQLite database.
+//!
+//! Only entries which differ from the stored ones are written, so a run
+//! which fetches a few entries doesn't rewrite the whole cache. Finding
+//! them still reads the whole database, see `SqliteStorage::store`.
+
+use super::Storage;
+use crate::data::{Issue, LinkedPr, PrState, PullRequest};
+use crate::fetcher::IssueData;
+use crate::query::Repo;
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use log::info;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Transaction};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Version of the database schema, stored as `user_version` of the database.
+const SCHEMA_VERSION: u32 = 7;
+
+/// Statements upgrading the schema from each version to the next one,
+/// starting from an empty database.
+const MIGRATIONS: [&str; SCHEMA_VERSION as usize] = [
+    SCHEMA_V1,
+    PULL_REQUESTS,
+    BODIES,
+    LINKED_PRS,
+    BODIES_FETCHED,
+    LINKED_PRS_TIME,
+    META,
+];
+
+const SCHEMA_V1: &str = "
+CREATE TABLE issues (
+    owner TEXT NOT NULL,
+    repo TEXT NOT NULL,
+    number INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    open INTEGER NOT NULL,
+    PRIMARY KEY (owner, repo, number)
+);
+CREATE TABLE labels (
+    owner TEXT NOT NULL,
+    repo TEXT NOT NULL,
+    label TEXT NOT NULL,
+    PRIMARY KEY (owner, repo, label)
+);
+CREATE TABLE label_issues (
+    owner TEXT NOT NULL,
+    repo TEXT NOT NULL,
+    label TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    number INTEGER NOT NULL,
+    PRIMARY KEY (owner, repo, label, position),
+    FOREIGN KEY (owner, repo, label) REFERENCES labels ON DELETE CASCADE
+);
+CREATE INDEX label_issues_by_issue ON label_issues (owner, repo, number);
+CREATE TABLE fetched (
+    owner TEXT NOT NULL,
+    repo TEXT NOT NULL,
+    label TEXT,
+    number INTEGER,
+    time TEXT NOT NULL,
+    CHECK ((label IS NULL) != (number IS NULL))
+);
+CREATE UNIQUE INDEX fetched_labels ON fetched (owner, repo, label) WHERE label IS NOT NULL;
+CREATE UNIQUE INDEX fetched_issues ON fetched (owner, repo, number) WHERE number IS NOT NULL;
+CREATE INDEX fetched_by_time ON fetched (time);
+";
+
+/// Merge state of pull requests, where `merged` is NULL for issues.
+const PULL_REQUESTS: &str = "
+ALTER TABLE issues ADD COLUMN merged INTEGER;
+ALTER TABLE issues ADD COLUMN milestone TEXT;
+";
+
+/// Bodies of tracking issues.
+const BODIES: &str = "
+CREATE TABLE bodies (
+    owner TEXT NOT NULL,
+    repo TEXT NOT NULL,
+    number INTEGER NOT NULL,
+    body TEXT NOT NULL,
+    PRIMARY KEY (owner, repo, number)
+);
+";
+
+/// Pull requests linked to tracking issues. Tracking issues without linked
+/// pull requests have a row in `linked_prs_fetched` only.
+const LINKED_PRS: &str = "
+CREATE TABLE linked_prs_fetched (
+    owner TEXT NOT NULL,
+    repo TEXT NOT NULL,
+    number INTEGER NOT NULL,
+    PRIMARY KEY (owner, repo, number)
+);
+CREATE TABLE linked_prs (
+    owner TEXT NOT NULL,
+    repo TEXT NOT NULL,
+    number INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    pr_owner TEXT NOT NULL,
+    pr_repo TEXT NOT NULL,
+    pr_number INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    state TEXT NOT NULL,
+    merged_at TEXT,
+    PRIMARY KEY (owner, repo, number, position),
+    FOREIGN KEY (owner, repo, number) REFERENCES linked_prs_fetched ON DELETE CASCADE
+);
+";
+
+/// Fetch times of bodies of tracking issues, NULL if unknown.
+const BODIES_FETCHED: &str = "
+ALTER TABLE bodies ADD COLUMN time TEXT;
+";
+
+/// Fetch times of pull requests linked to tracking issues, NULL if unknown.
+const LINKED_PRS_TIME: &str = "
+ALTER TABLE linked_prs_fetched ADD COLUMN time TEXT;
+";
+
+/// Values which are not per entry, like the latest tag.
+const META: &str = "
+CREATE TABLE meta (
+    key TEXT NOT NULL PRIMARY KEY,
+    value TEXT NOT NULL
+);
+";
+
+pub struct SqliteStorage {
+    conn: Connection,
+    /// Whether loading leaves the database as is, by rolling back the
+    /// migration instead of committing it.
+    read_only: bool,
+}
+
+impl SqliteStorage {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "foreign_keys", true)?;
+        Ok(SqliteStorage {
+            conn,
+            read_only: false,
+        })
+    }
+
+    /// Open an existing database for loading only, which never changes it.
+    ///
+    /// The database is opened read-only unless its schema needs migrating,
+    /// which is then done in a transaction that is rolled back.
+    pub fn open_read_only(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        let mut storage = SqliteStorage {
+            conn: Connection::open_with_flags(path, flags)?,
+            read_only: true,
+        };
+        if storage.schema_version()? < SCHEMA_VERSION {
+            let flags = OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+            storage.conn = Connection::open_with_flags(path, flags)?;
+        }
+        storage.conn.pragma_update(None, "foreign_keys", true)?;
+        Ok(storage)
+    }
+
+    fn schema_version(&self) -> Result<u32> {
+        let version = self
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            bail!(
+                "database schema version {} is newer than the supported version {}",
+                version,
+                SCHEMA_VERSION
+            );
+        }
+        Ok(version)
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn current_version(&self) -> u32 {
+        SCHEMA_VERSION
+    }
+
+    fn load(&mut self) -> Result<Option<(IssueData, u32)>> {
+        let version = self.schema_version()?;
+        if version == 0 {
+            return Ok(None);
+        }
+        let tx = self.conn.transaction()?;
+        migrate(&tx, version)?;
+        let data = read_data(&tx)?;
+        if self.read_only {
+            tx.rollback()?;
+        } else {
+            tx.commit()?;
+        }
+        Ok(Some((data, version)))
+    }
+
+    /// Write entries which differ from the stored ones.
+    ///
+    /// The stored entries are read back in full to compare against, as
+    /// `IssueData` doesn't track which entries changed, so storing costs
+    /// as much reading as loading the whole cache, but writing is limited
+    /// to what changed.
+    fn store(&mut self, data: &IssueData) -> Result<()> {
+        if self.read_only {
+            bail!("cache database is opened read-only");
+        }
+        let version = self.schema_version()?;
+        let tx = self.conn.transaction()?;
+        migrate(&tx, version)?;
+        let old = read_data(&tx)?;
+        write_issues(&tx, &old, data)?;
+        write_labels(&tx, &old, data)?;
+        write_fetched(&tx, &old, data)?;
+        write_bodies(&tx, &old, data)?;
+        write_linked_prs(&tx, &old, data)?;
+        write_meta(&tx, &old, data)?;
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+/// Upgrade the schema from the given version to the current version.
+fn migrate(tx: &Transaction, version: u32) -> Result<()> {
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        if i > 0 {
+            info!(
+                "migrating cache database from version {} to {}...",
+                i,
+                i + 1
+            );
+        }
+        tx.execute_batch(migration)?;
+    }
+    if version < SCHEMA_VERSION {
+        tx.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    }
+    Ok(())
+}
+
+fn read_data(conn: &Connection) -> Result<IssueData> {
+    let mut data = IssueData::default();
+
+    let mut stmt =
+        conn.prepare("SELECT owner, repo, number, title, open, merged, milestone FROM issues")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let repo = Repo::new(&row.get::<_, String>(0)?, &row.get::<_, String>(1)?);
+        let pull_request = match row.get::<_, Option<bool>>(5)? {
+            Some(merged) => Some(PullRequest {
+                merged,
+                milestone: row.get(6)?,
+            }),
+            None => None,
+        };
+        let issue = Issue {
+            number: row.get(2)?,
+            title: row.get(3)?,
+            open: row.get(4)?,
+            pull_request,
+        };
+        data.issues.insert((repo, issue.number), issue);
+    }
+
+    let mut stmt = conn.prepare("SELECT owner, repo, label FROM labels")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let repo = Repo::new(&row.get::<_, String>(0)?, &row.get::<_, String>(1)?);
+        data.labels.insert((repo, row.get(2)?), Vec::new());
+    }
+
+    let mut stmt =
+        conn.prepare("SELECT owner, repo, label, number FROM label_issues ORDER BY position")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let repo = Repo::new(&row.get::<_, String>(0)?, &row.get::<_, String>(1)?);
+        let label = row.get(2)?;
+        let number = row.get(3)?;
+        match data.labels.get_mut(&(repo, label)) {
+            Some(numbers) => numbers.push(number),
+            None => bail!("issue #{} listed under unknown label", number),
+        }
+    }
+
+    let mut stmt = conn.prepare("SELECT owner, repo, label, number, time FROM fetched")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let repo = Repo::new(&row.get::<_, String>(0)?, &row.get::<_, String>(1)?);
+        let time = DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)?.with_timezone(&Utc);
+        match (row.get::<_, Option<String>>(2)?, row.get(3)?) {
+            (Some(label), _) => {
+                data.labels_fetched.insert((repo, label), time);
+            }
+            (None, Some(number)) => {
+                data.issues_fetched.insert((repo, number), time);
+            }
+            (None, None) => bail!("fetch record of {} without key", repo),
+        }
+    }
+
+    let mut stmt = conn.prepare("SELECT owner, repo, number, body, time FROM bodies")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let repo = Repo::new(&row.get::<_, String>(0)?, &row.get::<_, String>(1)?);
+        let key = (repo, row.get(2)?);
+        if let Some(time) = row.get::<_, Option<String>>(4)? {
+            let time = DateTime::parse_from_rfc3339(&time)?.with_timezone(&Utc);
+            data.bodies_fetched.insert(key.clone(), time);
+        }
+        data.bodies.insert(key, row.get(3)?);
+    }
+
+    let mut stmt = conn.prepare("SELECT owner, repo, number, time FROM linked_prs_fetched")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let repo = Repo::new(&row.get::<_, String>(0)?, &row.get::<_, String>(1)?);
+        let key = (repo, row.get(2)?);
+        if let Some(time) = row.get::<_, Option<String>>(3)? {
+            let time = DateTime::parse_from_rfc3339(&time)?.with_timezone(&Utc);
+            data.linked_prs_fetched.insert(key.clone(), time);
+        }
+        data.linked_prs.insert(key, Vec::new());
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT owner, repo, number, pr_owner, pr_repo, pr_number, title, state, merged_at
+         FROM linked_prs ORDER BY position",
+    )?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let repo = Repo::new(&row.get::<_, String>(0)?, &row.get::<_, String>(1)?);
+        let number = row.get(2)?;
+        let state = match row.get::<_, String>(7)?.as_str() {
+            "open" => PrState::Open,
+            "merged" => PrState::Merged,
+            "closed" => PrState::Closed,
+            state => bail!("unknown pull request state `{}`", state),
+        };
+        let merged_at = match row.get::<_, Option<String>>(8)? {
+            Some(time) => Some(DateTime::parse_from_rfc3339(&time)?.with_timezone(&Utc)),
+            None => None,
+        };
+        let pr = LinkedPr {
+            repo: Repo::new(&row.get::<_, String>(3)?, &row.get::<_, String>(4)?),
+            number: row.get(5)?,
+            title: row.get(6)?,
+            state,
+            merged_at,
+        };
+        match data.linked_prs.get_mut(&(repo, number)) {
+            Some(prs) => prs.push(pr),
+            None => bail!("pull request linked to unknown issue #{}", number),
+        }
+    }
+
+    data.latest_tag = conn
+        .query_row(
+            "SELECT value FROM meta WHERE key = 'latest_tag'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(data)
+}
+
+fn write_issues(tx: &Transaction, old: &IssueData, new: &IssueData) -> Result<()> {
+    let mut upsert = tx.prepare(
+        "INSERT OR REPLACE INTO issues (owner, repo, number, title, open, merged, milestone)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?;
+    for ((repo, number), issue) in changed(&old.issues, &new.issues) {
+        let pr = issue.pull_request.as_ref();
+        upsert.execute(params![
+            repo.owner,
+            repo.name,
+            number,
+            issue.title,
+            issue.open,
+            pr.map(|pr| pr.merged),
+            pr.and_then(|pr| pr.milestone.as_ref()),
+        ])?;
+    }
+    let mut delete =
+        tx.prepare("DELETE FROM issues WHERE owner = ?1 AND repo = ?2 AND number = ?3")?;
+    for (repo, number) in removed(&old.issues, &new.issues) {
+        delete.execute(params![repo.owner, repo.name, number])?;
+    }
+    Ok(())
+}
+
+fn write_labels(tx: &Transaction, old: &IssueData, new: &IssueData) -> Result<()> {
+    let mut delete =
+        tx.prepare("DELETE FROM labels WHERE owner = ?1 AND repo = ?2 AND label = ?3")?;
+    let changed_keys = changed(&old.labels, &new.labels).map(|(key, _)| key);
+    for (repo, label) in changed_keys.chain(removed(&old.labels, &new.labels)) {
+        delete.execute(params![repo.owner, repo.name, label])?;
+    }
+    let mut insert_label =
+        tx.prepare("INSERT INTO labels (owner, repo, label) VALUES (?1, ?2, ?3)")?;
+    let mut insert = tx.prepare(
+        "INSERT INTO label_issues (owner, repo, label, position, number)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    for ((repo, label), numbers) in changed(&old.labels, &new.labels) {
+        insert_label.execute(params![repo.owner, repo.name, label])?;
+        for (position, number) in numbers.iter().enumerate() {
+            insert.execute(params![repo.owner, repo.name, label, position, number])?;
+        }
+    }
+    Ok(())
+}
+
+fn write_fetched(tx: &Transaction, old: &IssueData, new: &IssueData) -> Result<()> {
+    let mut upsert_label = tx.prepare(
+        "INSERT INTO fetched (owner, repo, label, time) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT (owner, repo, label) WHERE label IS NOT NULL DO UPDATE SET time = excluded.time",
+    )?;
+    for ((repo, label), time) in changed(&old.labels_fetched, &new.labels_fetched) {
+        upsert_label.execute(params![repo.owner, repo.name, label, time.to_rfc3339()])?;
+    }
+    let mut delete_label =
+        tx.prepare("DELETE FROM fetched WHERE owner = ?1 AND repo = ?2 AND label = ?3")?;
+    for (repo, label) in removed(&old.labels_fetched, &new.labels_fetched) {
+        delete_label.execute(params![repo.owner, repo.name, label])?;
+    }
+
+    let mut upsert_issue = tx.prepare(
+        "INSERT INTO fetched (owner, repo, number, time) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT (owner, repo, number) WHERE number IS NOT NULL DO UPDATE SET time = excluded.time",
+    )?;
+    for ((repo, number), time) in changed(&old.issues_fetched, &new.issues_fetched) {
+        upsert_issue.execute(params![repo.owner, repo.name, number, time.to_rfc3339()])?;
+    }
+    let mut delete_issue =
+        tx.prepare("DELETE FROM fetched WHERE owner = ?1 AND repo = ?2 AND number = ?3")?;
+    for (repo, number) in removed(&old.issues_fetched, &new.issues_fetched) {
+        delete_issue.execute(params![repo.owner, repo.name, number])?;
+    }
+    Ok(())
+}
+
+fn write_bodies(tx: &Transaction, old: &IssueData, new: &IssueData) -> Result<()> {
+    let mut upsert = tx.prepare(
+        "INSERT OR REPLACE INTO bodies (owner, repo, number, body, time)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    // Bodies are stored with their fetch times, so either changing means
+    // the row needs to be written.
+    let old_bodies = with_fetch_times(&old.bodies, &old.bodies_fetched);
+    let new_bodies = with_fetch_times(&new.bodies, &new.bodies_fetched);
+    for ((repo, number), (body, time)) in changed(&old_bodies, &new_bodies) {
+        let time = time.map(|time| time.to_rfc3339());
+        upsert.execute(params![repo.owner, repo.name, number, body, time])?;
+    }
+    let mut delete =
+        tx.prepare("DELETE FROM bodies WHERE owner = ?1 AND repo = ?2 AND number = ?3")?;
+    for (repo, number) in removed(&old.bodies, &new.bodies) {
+        delete.execute(params![repo.owner, repo.name, number])?;
+    }
+    Ok(())
+}
+
+fn write_linked_prs(tx: &Transaction, old: &IssueData, new: &IssueData) -> Result<()> {
+    let old_prs = with_fetch_times(&old.linked_prs, &old.linked_prs_fetched);
+    let new_prs = with_fetch_times(&new.linked_prs, &new.linked_prs_fetched);
+    let mut delete = tx
+        .prepare("DELETE FROM linked_prs_fetched WHERE owner = ?1 AND repo = ?2 AND number = ?3")?;
+    let changed_keys = changed(&old_prs, &new_prs).map(|(key, _)| key);
+    for (repo, number) in changed_keys.chain(removed(&old_prs, &new_prs)) {
+        delete.execute(params![repo.owner, repo.name, number])?;
+    }
+    let mut insert_fetched = tx.prepare(
+        "INSERT INTO linked_prs_fetched (owner, repo, number, time) VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    let mut insert = tx.prepare(
+        "INSERT INTO linked_prs (owner, repo, number, position, pr_owner, pr_repo, pr_number,
+                                 title, state, merged_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+    )?;
+    for ((repo, number), (prs, time)) in changed(&old_prs, &new_prs) {
+        let time = time.map(|time| time.to_rfc3339());
+        insert_fetched.execute(params![repo.owner, repo.name, number, time])?;
+        for (position, pr) in prs.iter().enumerate() {
+            let state = match pr.state {
+                PrState::Open => "open",
+                PrState::Merged => "merged",
+                PrState::Closed => "closed",
+            };
+            insert.execute(params![
+                repo.owner,
+                repo.name,
+                number,
+                position,
+                pr.repo.owner,
+                pr.repo.name,
+                pr.number,
+                pr.title,
+                state,
+                pr.merged_at.map(|time| time.to_rfc3339()),
+            ])?;
+        }
+    }
+    Ok(())
+}
+
+fn write_meta(tx: &Transaction, old: &IssueData, new: &IssueData) -> Result<()> {
+    if old.latest_tag == new.latest_tag {
+        return Ok(());
+    }
+    match &new.latest_tag {
+        Some(tag) => tx.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES ('latest_tag', ?1)",
+            params![tag],
+        )?,
+        None => tx.execute("DELETE FROM meta WHERE key = 'latest_tag'", [])?,
+    };
+    Ok(())
+}
+
+/// Pair each entry with its fetch time, if known.
+fn with_fetch_times<'a, K: Ord + Clone, V>(
+    entries: &'a BTreeMap<K, V>,
+    fetched: &'a BTreeMap<K, DateTime<Utc>>,
+) -> BTreeMap<K, (&'a V, Option<&'a DateTime<Utc>>)> {
+    entries
+        .iter()
+        .map(|(key, value)| (key.clone(), (value, fetched.get(key))))
+        .collect()
+}
+
+/// Entries in `new` which are missing from or different in `old`.
+fn changed<'a, K: Ord, V: PartialEq>(
+    old: &'a BTreeMap<K, V>,
+    new: &'a BTreeMap<K, V>,
+) -> impl Iterator<Item = (&'a K, &'a V)> {
+    new.iter()
+        .filter(move |(key, value)| old.get(key) != Some(value))
+}
+
+/// Keys in `old` which are missing from `new`.
+fn removed<'a, K: Ord, V>(
+    old: &'a BTreeMap<K, V>,
+    new: &'a BTreeMap<K, V>,
+) -> impl Iterator<Item = &'a K> {
+    old.keys().filter(move |key| !new.contains_key(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::json::JsonStorage;
+
+    fn time(day: u32) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(&format!("2020-01-{:02}T00:00:00Z", day))
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn issue(number: u32, pull_request: Option<PullRequest>) -> Issue {
+        Issue {
+            number,
+            title: format!("Issue {}", number),
+            open: number > 3,
+            pull_request,
+        }
+    }
+
+    fn sample() -> IssueData {
+        let rust = Repo::new("rust-lang", "rust");
+        let rfcs = Repo::new("rust-lang", "rfcs");
+        let mut data = IssueData::default();
+        data.labels
+            .insert((rust.clone(), "A-a".to_string()), vec![3, 1]);
+        data.labels
+            .insert((rust.clone(), "A-b".to_string()), vec![]);
+        data.labels_fetched
+            .insert((rust.clone(), "A-a".to_string()), time(1));
+        data.issues.insert((rust.clone(), 1), issue(1, None));
+        data.issues.insert((rust.clone(), 3), issue(3, None));
+        let pr = PullRequest {
+            merged: true,
+            milestone: Some("1.39".to_string()),
+        };
+        data.issues.insert((rust.clone(), 4), issue(4, Some(pr)));
+        let pr = PullRequest {
+            merged: false,
+            milestone: None,
+        };
+        data.issues.insert((rust.clone(), 5), issue(5, Some(pr)));
+        data.issues.insert((rfcs.clone(), 1), issue(1, None));
+        data.issues_fetched.insert((rust.clone(), 4), time(2));
+        data.issues_fetched.insert((rfcs, 1), time(3));
+        data.bodies.insert((rust.clone(), 1), "- [x] a".to_string());
+        data.bodies.insert((rust.clone(), 3), "body".to_string());
+        data.bodies_fetched.insert((rust.clone(), 1), time(4));
+        let linked_pr = |number, state, merged_at| LinkedPr {
+            repo: Repo::new("rust-lang", "cargo"),
+            number,
+            title: format!("PR {}", number),
+            state,
+            merged_at,
+        };
+        let prs = vec![
+            linked_pr(7, PrState::Merged, Some(time(5))),
+            linked_pr(6, PrState::Open, None),
+            linked_pr(8, PrState::Closed, None),
+        ];
+        data.linked_prs.insert((rust.clone(), 1), prs);
+        data.linked_prs.insert((rust.clone(), 3), vec![]);
+        data.linked_prs_fetched.insert((rust, 3), time(6));
+        data.latest_tag = Some("1.40.0".to_string());
+        data
+    }
+
+    fn count(storage: &SqliteStorage, table: &str) -> u32 {
+        let sql = format!("SELECT count(*) FROM {}", table);
+        storage.conn.query_row(&sql, [], |row| row.get(0)).unwrap()
+    }
+
+    fn user_version(conn: &Connection) -> u32 {
+        conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn empty_database() {
+        let mut storage = SqliteStorage::open(":memory:").unwrap();
+        assert!(storage.load().unwrap().is_none());
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut storage = SqliteStorage::open(":memory:").unwrap();
+        storage.store(&sample()).unwrap();
+        let (data, version) = storage.load().unwrap().unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+        assert_eq!(data, sample());
+    }
+
+    #[test]
+    fn store_changes() {
+        let mut storage = SqliteStorage::open(":memory:").unwrap();
+        storage.store(&sample()).unwrap();
+        let rust = Repo::new("rust-lang", "rust");
+        let mut data = sample();
+        data.labels
+            .insert((rust.clone(), "A-a".to_string()), vec![1]);
+        data.labels_fetched
+            .insert((rust.clone(), "A-b".to_string()), time(7));
+        data.issues.get_mut(&(rust.clone(), 1)).unwrap().open = true;
+        data.bodies_fetched.insert((rust.clone(), 3), time(7));
+        data.linked_prs.get_mut(&(rust, 1)).unwrap().pop();
+        data.latest_tag = Some("1.41.0".to_string());
+        storage.store(&data).unwrap();
+        assert_eq!(storage.load().unwrap().unwrap().0, data);
+    }
+
+    #[test]
+    fn store_removals() {
+        let mut storage = SqliteStorage::open(":memory:").unwrap();
+        storage.store(&sample()).unwrap();
+        let mut data = sample();
+        data.labels.clear();
+        data.labels_fetched.clear();
+        data.issues.retain(|(_, number), _| *number == 4);
+        data.issues_fetched.clear();
+        data.bodies.clear();
+        data.bodies_fetched.clear();
+        data.linked_prs.clear();
+        data.linked_prs_fetched.clear();
+        data.latest_tag = None;
+        storage.store(&data).unwrap();
+        assert_eq!(storage.load().unwrap().unwrap().0, data);
+        for table in [
+            "labels",
+            "label_issues",
+            "fetched",
+            "bodies",
+            "linked_prs_fetched",
+            "linked_prs",
+            "meta",
+        ] {
+            assert_eq!(count(&storage, table), 0, "{} is not empty", table);
+        }
+        assert_eq!(count(&storage, "issues"), 1);
+    }
+
+    #[test]
+    fn migrate_from_every_version() {
+        for version in 1..=SCHEMA_VERSION {
+            let conn = Connection::open_in_memory().unwrap();
+            for migration in &MIGRATIONS[..version as usize] {
+                conn.execute_batch(migration).unwrap();
+            }
+            conn.pragma_update(None, "user_version", version).unwrap();
+            conn.execute(
+                "INSERT INTO issues (owner, repo, number, title, open)
+                 VALUES ('rust-lang', 'rust', 1, 'Issue 1', 0)",
+                [],
+            )
+            .unwrap();
+            let mut storage = SqliteStorage {
+                conn,
+                read_only: false,
+            };
+            let (data, original) = storage.load().unwrap().unwrap();
+            assert_eq!(original, version);
+            assert_eq!(user_version(&storage.conn), SCHEMA_VERSION);
+            let key = (Repo::new("rust-lang", "rust"), 1);
+            assert_eq!(data.issues[&key], issue(1, None));
+            // The migrated schema has everything the latest one has.
+            storage.store(&sample()).unwrap();
+            assert_eq!(storage.load().unwrap().unwrap().0, sample());
+        }
+    }
+
+    #[test]
+    fn newer_version_is_error() {
+        let mut storage = SqliteStorage::open(":memory:").unwrap();
+        storage
+            .conn
+            .pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+        assert!(storage.load().is_err());
+        assert!(storage.store(&sample()).is_err());
+    }
+
+    #[test]
+    fn read_only_load_does_not_migrate() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.sqlite");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(SCHEMA_V1).unwrap();
+        conn.pragma_update(None, "user_version", 1).unwrap();
+        drop(conn);
+        let mut storage = SqliteStorage::open_read_only(&path).unwrap();
+        assert_eq!(storage.load().unwrap().unwrap().1, 1);
+        assert!(storage.store(&sample()).is_err());
+        drop(storage);
+        assert_eq!(user_version(&Connection::open(&path).unwrap()), 1);
+    }
+
+    #[test]
+    fn same_as_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storages: Vec<Box<dyn Storage>> = vec![
+            Box::new(JsonStorage::new(dir.path().join("cache.json"))),
+            Box::new(SqliteStorage::open(dir.path().join("cache.sqlite")).unwrap()),
+        ];
+        for storage in storages.iter_mut() {
+            storage.store(&sample()).unwrap();
+        }
+        let loaded: Vec<_> = storages
+            .iter_mut()
+            .map(|storage| storage.load().unwrap().unwrap().0)
+            .collect();
+        assert_eq!(loaded[0], sample());
+        assert_eq!(loaded[1], sample());
+        // Converting one into the other keeps everything.
+        storages[1].store(&IssueData::default()).unwrap();
+        storages[1].store(&loaded[0]).unwrap();
+        assert_eq!(storages[1].load().unwrap().unwrap().0, sample());
+    }
+}
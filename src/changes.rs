@@ -0,0 +1,377 @@
+//! Changes of item states between builds.
+//!
+//! The state of the previous build is read back from its API files, and
+//! detected changes are accumulated in the change log from the same place.
+//! They come from the output directory by default, and CI reads them from
+//! the deployed site, so that the log persists across builds on fresh
+//! checkouts.
+
+use crate::data::output::{Item, VersionState};
+use crate::data::IssueId;
+use crate::page_gen::api::{CHANGES_FILE, ITEMS_FILE};
+use crate::previous::PreviousSource;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Number of days changes are kept in the change log.
+const LOG_DAYS: i64 = 365;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct StabilizationState {
+    pub version: String,
+    pub state: VersionState,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Change {
+    pub item: String,
+    pub time: DateTime<Utc>,
+    #[serde(flatten)]
+    pub kind: ChangeKind,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChangeKind {
+    Stabilization {
+        from: Option<StabilizationState>,
+        to: Option<StabilizationState>,
+    },
+    /// The tracking issue of the item was closed.
+    TrackingClosed { issue: IssueId },
+    /// A new issue appeared under the label of the item.
+    NewIssue {
+        label: String,
+        issue: IssueId,
+        title: String,
+    },
+}
+
+impl ChangeKind {
+    /// Describe the change in a short sentence fragment.
+    pub fn describe(&self) -> String {
+        match self {
+            ChangeKind::Stabilization { from, to } => {
+                let describe = |state: &Option<StabilizationState>| match state {
+                    Some(state) => format!("{} {}", state.state.name(), state.version),
+                    None => "not stabilized".to_string(),
+                };
+                format!("{} \u{2192} {}", describe(from), describe(to))
+            }
+            ChangeKind::TrackingClosed { issue } => format!("tracking issue #{} closed", issue),
+            ChangeKind::NewIssue {
+                label,
+                issue,
+                title,
+            } => format!("new issue #{} under {}: {}", issue, label, title),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PreviousItems {
+    items: Vec<PreviousItem>,
+}
+
+#[derive(Deserialize)]
+struct PreviousItem {
+    id: String,
+    stabilized: Option<StabilizationState>,
+    tracking: Option<PreviousIssue>,
+    issue_label: Option<String>,
+    #[serde(default)]
+    issues: Vec<PreviousIssue>,
+}
+
+#[derive(Deserialize)]
+struct PreviousIssue {
+    number: IssueId,
+    state: PreviousIssueState,
+}
+
+#[derive(Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum PreviousIssueState {
+    Open,
+    Closed,
+}
+
+#[derive(Deserialize)]
+struct ChangeLog {
+    changes: Vec<Change>,
+}
+
+/// State of the previous build.
+pub struct Previous {
+    items: Option<HashMap<String, PreviousItem>>,
+    changes: Vec<Change>,
+}
+
+impl Previous {
+    /// Load the state of the previous build from the given source.
+    ///
+    /// Files missing from a directory source are treated as empty, as
+    /// there was no previous build. Anything else failing is an error, so
+    /// that the change log is never replaced with an empty one.
+    pub async fn load(source: &PreviousSource, client: &Client) -> Result<Self> {
+        let items = source
+            .read_json::<PreviousItems>(client, ITEMS_FILE)
+            .await?
+            .map(|items| {
+                let items = items.items.into_iter();
+                items.map(|item| (item.id.clone(), item)).collect()
+            });
+        let changes = source
+            .read_json::<ChangeLog>(client, CHANGES_FILE)
+            .await?
+            .map_or_else(Vec::new, |log| log.changes);
+        Ok(Previous { items, changes })
+    }
+
+    /// Compare the given items against the previous build and return the
+    /// updated change log, newest first, without changes older than
+    /// `LOG_DAYS` days.
+    pub fn update(self, sections: &BTreeMap<String, Vec<Item>>, time: DateTime<Utc>) -> Vec<Change> {
+        let mut changes = self.changes;
+        if let Some(previous) = &self.items {
+            let mut items: Vec<_> = sections.values().flatten().collect();
+            while let Some(item) = items.pop() {
+                items.extend(item.deps.iter());
+                let previous = match previous.get(&item.id) {
+                    Some(previous) => previous,
+                    None => continue,
+                };
+                let stabilized = item.stabilized.as_ref().map(|s| StabilizationState {
+                    version: s.version.clone(),
+                    state: s.state,
+                });
+                let mut push = |kind| {
+                    changes.push(Change {
+                        item: item.id.clone(),
+                        time,
+                        kind,
+                    })
+                };
+                if previous.stabilized != stabilized {
+                    push(ChangeKind::Stabilization {
+                        from: previous.stabilized.clone(),
+                        to: stabilized,
+                    });
+                }
+                if let (Some(before), Some(after)) = (&previous.tracking, &item.tracking) {
+                    if before.number == after.number
+                        && before.state == PreviousIssueState::Open
+                        && !after.open
+                    {
+                        push(ChangeKind::TrackingClosed {
+                            issue: after.number,
+                        });
+                    }
+                }
+                // Everything would be new if the label itself changed.
+                if let Some(label) = &item.issue_label {
+                    if previous.issue_label.as_ref() == Some(label) {
+                        let known: HashSet<_> =
+                            previous.issues.iter().map(|issue| issue.number).collect();
+                        for issue in item.issues.iter() {
+                            if !known.contains(&issue.number) {
+                                push(ChangeKind::NewIssue {
+                                    label: label.clone(),
+                                    issue: issue.number,
+                                    title: issue.title.clone(),
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        changes.retain(|change| time - change.time <= Duration::days(LOG_DAYS));
+        changes.sort_by(|a, b| b.time.cmp(&a.time).then_with(|| a.item.cmp(&b.item)));
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::output::Stabilization;
+    use crate::data::Issue;
+    use chrono::{NaiveDate, TimeZone};
+
+    fn issue(number: IssueId, open: bool) -> Issue {
+        Issue {
+            number,
+            title: format!("issue {}", number),
+            open,
+            pull_request: None,
+        }
+    }
+
+    fn time(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2020, 1, day, 0, 0, 0).unwrap()
+    }
+
+    fn previous(items: &str, changes: Vec<Change>) -> Previous {
+        let items: PreviousItems = serde_json::from_str(items).unwrap();
+        let items = items.items.into_iter();
+        Previous {
+            items: Some(items.map(|item| (item.id.clone(), item)).collect()),
+            changes,
+        }
+    }
+
+    fn sections(items: Vec<Item>) -> BTreeMap<String, Vec<Item>> {
+        BTreeMap::from([("Section".to_string(), items)])
+    }
+
+    fn stabilized(version: &str, state: VersionState) -> Stabilization {
+        Stabilization {
+            state,
+            version: version.to_string(),
+            release_date: NaiveDate::from_ymd_opt(2020, 1, 1),
+            pr: issue(1, false),
+        }
+    }
+
+    #[test]
+    fn stabilization() {
+        let previous = previous(
+            r#"{"items": [
+                {"id": "a", "stabilized": null, "tracking": null, "issue_label": null},
+                {"id": "b", "stabilized": {"version": "1.40", "state": "beta"},
+                 "tracking": null, "issue_label": null},
+                {"id": "c", "stabilized": {"version": "1.39", "state": "stable"},
+                 "tracking": null, "issue_label": null}
+            ]}"#,
+            vec![],
+        );
+        let mut a = Item::new_for_test("a", "A");
+        a.stabilized = Some(stabilized("1.41", VersionState::Nightly));
+        let mut b = Item::new_for_test("b", "B");
+        b.stabilized = Some(stabilized("1.40", VersionState::Stable));
+        let mut c = Item::new_for_test("c", "C");
+        c.stabilized = Some(stabilized("1.39", VersionState::Stable));
+        let changes = previous.update(&sections(vec![a, b, c]), time(2));
+        let changes: Vec<_> = changes
+            .iter()
+            .map(|change| (change.item.as_str(), change.kind.describe()))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("a", "not stabilized \u{2192} nightly 1.41".to_string()),
+                ("b", "beta 1.40 \u{2192} stable 1.40".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn tracking_closed() {
+        let previous = previous(
+            r#"{"items": [
+                {"id": "a", "stabilized": null, "issue_label": null,
+                 "tracking": {"number": 1, "state": "open"}},
+                {"id": "b", "stabilized": null, "issue_label": null,
+                 "tracking": {"number": 2, "state": "closed"}},
+                {"id": "c", "stabilized": null, "issue_label": null,
+                 "tracking": {"number": 3, "state": "open"}}
+            ]}"#,
+            vec![],
+        );
+        let mut a = Item::new_for_test("a", "A");
+        a.tracking = Some(issue(1, false));
+        let mut b = Item::new_for_test("b", "B");
+        b.tracking = Some(issue(2, false));
+        // A different tracking issue which is closed isn't a change.
+        let mut c = Item::new_for_test("c", "C");
+        c.tracking = Some(issue(4, false));
+        let changes = previous.update(&sections(vec![a, b, c]), time(2));
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].item, "a");
+        assert!(matches!(
+            changes[0].kind,
+            ChangeKind::TrackingClosed { issue: 1 }
+        ));
+    }
+
+    #[test]
+    fn new_issue() {
+        let previous = previous(
+            r#"{"items": [
+                {"id": "a", "stabilized": null, "tracking": null, "issue_label": "A-a",
+                 "issues": [{"number": 1, "state": "open"}]},
+                {"id": "b", "stabilized": null, "tracking": null, "issue_label": "A-b",
+                 "issues": [{"number": 1, "state": "open"}]}
+            ]}"#,
+            vec![],
+        );
+        let mut a = Item::new_for_test("a", "A");
+        a.issue_label = Some("A-a".to_string());
+        a.issues = vec![issue(1, false), issue(2, true)];
+        // Issues are all new when the label changes, which isn't reported.
+        let mut b = Item::new_for_test("b", "B");
+        b.issue_label = Some("A-c".to_string());
+        b.issues = vec![issue(3, true)];
+        let changes = previous.update(&sections(vec![a, b]), time(2));
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].item, "a");
+        assert!(matches!(
+            &changes[0].kind,
+            ChangeKind::NewIssue { label, issue: 2, .. } if label == "A-a"
+        ));
+    }
+
+    #[test]
+    fn keeps_log_newest_first() {
+        let old = Change {
+            item: "a".to_string(),
+            time: time(1),
+            kind: ChangeKind::TrackingClosed { issue: 1 },
+        };
+        let previous = previous(
+            r#"{"items": [
+                {"id": "a", "stabilized": null, "tracking": null, "issue_label": null}
+            ]}"#,
+            vec![old],
+        );
+        let mut a = Item::new_for_test("a", "A");
+        a.stabilized = Some(stabilized("1.41", VersionState::Nightly));
+        let changes = previous.update(&sections(vec![a]), time(2));
+        let times: Vec<_> = changes.iter().map(|change| change.time).collect();
+        assert_eq!(times, [time(2), time(1)]);
+    }
+
+    #[test]
+    fn prunes_old_changes() {
+        let change = |time| Change {
+            item: "a".to_string(),
+            time,
+            kind: ChangeKind::TrackingClosed { issue: 1 },
+        };
+        let now = time(2);
+        let kept = now - Duration::days(LOG_DAYS);
+        let pruned = kept - Duration::seconds(1);
+        let previous = Previous {
+            items: None,
+            changes: vec![change(pruned), change(kept), change(now)],
+        };
+        let changes = previous.update(&sections(vec![]), now);
+        let times: Vec<_> = changes.iter().map(|change| change.time).collect();
+        assert_eq!(times, [now, kept]);
+    }
+
+    #[test]
+    fn no_previous_items() {
+        let previous = Previous {
+            items: None,
+            changes: vec![],
+        };
+        let mut a = Item::new_for_test("a", "A");
+        a.stabilized = Some(stabilized("1.41", VersionState::Nightly));
+        assert!(previous.update(&sections(vec![a]), time(2)).is_empty());
+    }
+}
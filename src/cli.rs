@@ -0,0 +1,112 @@
+use crate::cache::command::CacheCommand;
+use crate::data::IssueId;
+use crate::diff;
+use crate::history::command::HistoryCommand;
+use crate::previous::PreviousSource;
+use crate::report::Format;
+use crate::schema::SchemaKind;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(
+    about = "Static page generator for areweasyncyet.rs",
+    args_conflicts_with_subcommands = true
+)]
+pub struct Args {
+    #[command(flatten)]
+    pub build: BuildArgs,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(clap::Args)]
+pub struct BuildArgs {
+    /// Directory of templates shadowing the built-in ones with the same name
+    #[arg(long, value_name = "DIR")]
+    pub templates: Option<PathBuf>,
+    /// Directory of static files shadowing the built-in ones with the same path
+    #[arg(long = "static", value_name = "DIR")]
+    pub static_dir: Option<PathBuf>,
+    /// Unix timestamp to use as the build time instead of the current time
+    #[arg(long, env = "SOURCE_DATE_EPOCH", value_name = "SECONDS")]
+    pub source_date_epoch: Option<i64>,
+    /// Checkout of rust-lang/rfcs to read unresolved questions of RFCs from.
+    /// The output then depends on the checked out revision, so pin it for
+    /// reproducible builds
+    #[arg(long, env = "RFCS_DIR", value_name = "DIR")]
+    pub rfcs_dir: Option<PathBuf>,
+    /// Output directory or URL of a deployed site to read the state of the
+    /// previous build from, which changes are detected against.
+    /// Defaults to the output directory
+    #[arg(long, value_name = "DIR|URL")]
+    pub previous: Option<PreviousSource>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Add an item for a tracking issue in rust-lang/rust to data.yml
+    Add {
+        /// Number of the tracking issue
+        #[arg(long)]
+        tracking: IssueId,
+        /// Section to append the item to
+        #[arg(long)]
+        section: String,
+    },
+    /// Generate the site into the output directory (default)
+    Build(BuildArgs),
+    /// Inspect and maintain the cache file
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+    /// Validate data.yml and posts.yml against their schemas
+    Check,
+    /// List open tracking issues with the given labels which are not in data.yml
+    Discover {
+        /// Label in rust-lang/rust to search tracking issues with
+        #[arg(
+            long = "label",
+            value_name = "LABEL",
+            default_values = ["A-async-await", "WG-async"]
+        )]
+        labels: Vec<String>,
+        /// Add the tracking issues to data.yml as draft items
+        #[arg(long, requires = "section")]
+        write: bool,
+        /// Section to add draft items to
+        #[arg(long, requires = "write")]
+        section: Option<String>,
+    },
+    /// Compare two cache files, or two history snapshots given by ids
+    Diff {
+        old: String,
+        new: String,
+        #[arg(long, value_enum, default_value = "text")]
+        format: diff::Format,
+    },
+    /// Query snapshots of the tracker state recorded on builds
+    History {
+        #[command(subcommand)]
+        command: HistoryCommand,
+    },
+    /// Render a status report of all items
+    Report {
+        #[arg(long, value_enum, default_value = "markdown")]
+        format: Format,
+        /// Write the report into the given file instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Print the JSON Schema of data.yml or posts.yml
+    Schema {
+        #[arg(value_enum, default_value = "data")]
+        kind: SchemaKind,
+        /// Write all schemas into the schema directory instead
+        #[arg(long)]
+        write: bool,
+    },
+    /// Print suggested updates of data.yml as a diff, based on GitHub state
+    Suggest,
+}
@@ -0,0 +1,337 @@
+//! Line-based editing of data.yml.
+//!
+//! Edits are applied to lines of the file rather than through serde_yaml,
+//! so that comments, blank lines and quoting elsewhere are kept as is.
+//! Only the block style used in data.yml is understood.
+
+use anyhow::{bail, Context, Result};
+
+pub struct Document {
+    lines: Vec<String>,
+}
+
+/// Range of lines of an item, including its deps.
+#[derive(Clone, Copy, Debug)]
+pub struct ItemSpan {
+    /// Line of the `- ` marker.
+    pub start: usize,
+    /// Line after the last non-blank line of the item.
+    pub end: usize,
+    /// Indentation of the fields of the item.
+    indent: usize,
+}
+
+impl Document {
+    pub fn new(text: &str) -> Self {
+        Document {
+            lines: text.lines().map(str::to_string).collect(),
+        }
+    }
+
+    pub fn text(&self) -> String {
+        let mut text = self.lines.join("\n");
+        text.push('\n');
+        text
+    }
+
+    /// List all items including deps with their ids, in document order.
+    pub fn items(&self) -> Vec<(String, ItemSpan)> {
+        let mut result = Vec::new();
+        for (start, line) in self.lines.iter().enumerate() {
+            let trimmed = line.trim_start();
+            if !trimmed.starts_with("- ") || is_comment(trimmed) {
+                continue;
+            }
+            let dash_indent = indent_of(line);
+            let indent = dash_indent + 2;
+            let end = self.block_end(start, dash_indent);
+            let span = ItemSpan { start, end, indent };
+            let title = self.field_value(span, "title");
+            let id = match self.field_value(span, "id") {
+                Some(id) => id,
+                None => match title {
+                    Some(title) => super::input::slugify(&title),
+                    None => continue,
+                },
+            };
+            result.push((id, span));
+        }
+        result
+    }
+
+    pub fn find_item(&self, id: &str) -> Result<ItemSpan> {
+        let item = self.items().into_iter().find(|(item_id, _)| item_id == id);
+        item.map(|(_, span)| span)
+            .with_context(|| format!("item `{}` not found in data.yml", id))
+    }
+
+    /// Set the field at the given path of the item to the given YAML value,
+    /// replacing the existing value or inserting a new line.
+    ///
+    /// All mappings on the path except the last field need to exist.
+    pub fn set_field(&mut self, item: ItemSpan, path: &[&str], value: &str) -> Result<()> {
+        let (key, parents) = path.split_last().context("empty field path")?;
+        let (mut start, mut end, mut indent) = (item.start, item.end, item.indent);
+        for parent in parents {
+            let line = match self.find_key(start, end, indent, parent) {
+                Some(line) => line,
+                None => bail!("`{}` not found in item", parent),
+            };
+            start = line;
+            end = self.block_end(line, indent);
+            indent = self.child_indent(line, end).unwrap_or(indent + 2);
+        }
+        let new_line = format!("{}{}: {}", " ".repeat(indent), key, value);
+        match self.find_key(start, end, indent, key) {
+            Some(line) => self.lines[line] = new_line,
+            None => self.lines.insert(start + 1, new_line),
+        }
+        Ok(())
+    }
+
+    /// Insert a comment line right above the item, unless the comments
+    /// above it already contain the same line.
+    pub fn insert_comment(&mut self, item: ItemSpan, comment: &str) {
+        let line = format!("{}# {}", " ".repeat(item.indent - 2), comment);
+        let exists = self.lines[..item.start]
+            .iter()
+            .rev()
+            .take_while(|existing| is_comment(existing.trim_start()))
+            .any(|existing| *existing == line);
+        if !exists {
+            self.lines.insert(item.start, line);
+        }
+    }
+
+    /// Append an item given as YAML lines without indentation to the end of
+    /// the given top-level section.
+    pub fn append_item(&mut self, section: &str, item: &[String]) -> Result<()> {
+        let header = format!("{}:", section);
+        let start = self
+            .lines
+            .iter()
+            .position(|line| line.trim_end() == header)
+            .with_context(|| format!("section `{}` not found in data.yml", section))?;
+        let end = self.section_end(start);
+        // Follow the style of existing items in the section.
+        let dash_indent = self.lines[start + 1..end]
+            .iter()
+            .find(|line| line.trim_start().starts_with("- "))
+            .map_or(2, |line| indent_of(line));
+        let separated = self.lines[start + 1..end]
+            .iter()
+            .any(|line| line.trim().is_empty());
+        let mut lines = Vec::new();
+        if separated || end == start + 1 {
+            lines.push(String::new());
+        }
+        for (i, line) in item.iter().enumerate() {
+            let prefix = if i == 0 { "- " } else { "  " };
+            lines.push(format!("{}{}{}", " ".repeat(dash_indent), prefix, line));
+        }
+        self.lines.splice(end..end, lines);
+        Ok(())
+    }
+
+    fn field_value(&self, item: ItemSpan, key: &str) -> Option<String> {
+        let line = self.find_key(item.start, item.end, item.indent, key)?;
+        let (_, value) = self.lines[line].split_once(':')?;
+        serde_yaml::from_str(value.trim()).ok()
+    }
+
+    /// Find the line of the given key at the given indentation within
+    /// the range, where the first line may start with a `- ` marker.
+    fn find_key(&self, start: usize, end: usize, indent: usize, key: &str) -> Option<usize> {
+        let prefix = format!("{}:", key);
+        (start..end).find(|&i| {
+            let line = &self.lines[i];
+            let content = if i == start && line.trim_start().starts_with("- ") {
+                line.trim_start()[2..].trim_start()
+            } else if indent_of(line) == indent {
+                line.trim_start()
+            } else {
+                return false;
+            };
+            content == prefix || content.starts_with(&format!("{} ", prefix))
+        })
+    }
+
+    /// Find the end of the block starting at the given line, which is
+    /// the line after the last non-blank line indented deeper than `indent`.
+    fn block_end(&self, start: usize, indent: usize) -> usize {
+        let mut end = start + 1;
+        for (i, line) in self.lines.iter().enumerate().skip(start + 1) {
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() || is_comment(trimmed) {
+                continue;
+            }
+            if indent_of(line) <= indent {
+                break;
+            }
+            end = i + 1;
+        }
+        end
+    }
+
+    /// Find the end of the top-level section starting at the given line,
+    /// whose items may be at the same indentation as its key.
+    fn section_end(&self, start: usize) -> usize {
+        let mut end = start + 1;
+        for (i, line) in self.lines.iter().enumerate().skip(start + 1) {
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() || is_comment(trimmed) {
+                continue;
+            }
+            if indent_of(line) == 0 && !trimmed.starts_with("- ") {
+                break;
+            }
+            end = i + 1;
+        }
+        end
+    }
+
+    fn child_indent(&self, start: usize, end: usize) -> Option<usize> {
+        self.lines[start + 1..end]
+            .iter()
+            .find(|line| !line.trim().is_empty() && !is_comment(line.trim_start()))
+            .map(|line| indent_of(line))
+    }
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn is_comment(trimmed: &str) -> bool {
+    trimmed.starts_with('#')
+}
+
+/// Format a string as a double-quoted YAML scalar.
+pub fn quote(value: &str) -> String {
+    serde_json::to_string(value).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn append_separated_by_blank_lines() {
+        let text = "\
+# Comment at the top
+foo:
+
+  - title: A
+    tracking: 1
+
+  # Comment on B
+  - title: B
+    rfc: \"2394\"
+
+bar:
+  - title: C
+";
+        let mut document = Document::new(text);
+        document
+            .append_item("foo", &lines(&["title: New", "tracking: 3"]))
+            .unwrap();
+        assert_eq!(
+            document.text(),
+            "\
+# Comment at the top
+foo:
+
+  - title: A
+    tracking: 1
+
+  # Comment on B
+  - title: B
+    rfc: \"2394\"
+
+  - title: New
+    tracking: 3
+
+bar:
+  - title: C
+"
+        );
+    }
+
+    #[test]
+    fn append_without_blank_lines() {
+        let text = "\
+foo:
+- title: A
+  deps:
+    - title: A1
+- title: B
+bar:
+- title: C
+";
+        let mut document = Document::new(text);
+        document
+            .append_item("foo", &lines(&["title: New"]))
+            .unwrap();
+        assert_eq!(
+            document.text(),
+            "\
+foo:
+- title: A
+  deps:
+    - title: A1
+- title: B
+- title: New
+bar:
+- title: C
+"
+        );
+    }
+
+    #[test]
+    fn append_to_last_section() {
+        let text = "foo:\n    - title: A\n    - title: B\n# Trailing comment\n";
+        let mut document = Document::new(text);
+        document
+            .append_item("foo", &lines(&["title: New"]))
+            .unwrap();
+        assert_eq!(
+            document.text(),
+            "foo:\n    - title: A\n    - title: B\n    - title: New\n# Trailing comment\n"
+        );
+    }
+
+    #[test]
+    fn append_to_empty_section() {
+        let text = "foo:\nbar:\n  - title: A\n";
+        let mut document = Document::new(text);
+        document
+            .append_item("foo", &lines(&["title: New", "tracking: 1"]))
+            .unwrap();
+        assert_eq!(
+            document.text(),
+            "foo:\n\n  - title: New\n    tracking: 1\nbar:\n  - title: A\n"
+        );
+    }
+
+    #[test]
+    fn append_to_unknown_section() {
+        let mut document = Document::new("foo:\n  - title: A\n");
+        assert!(document
+            .append_item("bar", &lines(&["title: New"]))
+            .is_err());
+    }
+
+    #[test]
+    fn appended_item_is_found() {
+        let mut document = Document::new("foo:\n  - title: A\n");
+        document
+            .append_item("foo", &lines(&["title: \"`async` closures\""]))
+            .unwrap();
+        let ids: Vec<_> = document.items().into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, ["a", "async-closures"]);
+    }
+}
@@ -1,38 +1,138 @@
 use super::{IssueId, Link};
 use crate::query::Repo;
 use crate::{RFC_REPO, RUSTC_REPO};
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, NumberValidation, Schema, SchemaObject, StringValidation};
+use schemars::JsonSchema;
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashSet};
 use std::fs::File;
 use std::path::Path;
 
-pub struct InputData(pub HashMap<String, Vec<Item>>);
+pub struct InputData(pub BTreeMap<String, Vec<Item>>);
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct Item {
+    /// Title of the item, text in backquotes is rendered as code.
     pub title: String,
+    /// Identifier of the item used in URLs, derived from the title if omitted.
+    #[schemars(regex(pattern = r"^[a-z0-9]+(-[a-z0-9]+)*$"))]
+    pub id: Option<String>,
+    /// RFC number, or page name of a merged RFC with optional fragment.
+    #[serde(default)]
+    #[schemars(schema_with = "rfc_schema")]
     pub rfc: Option<String>,
+    /// Tracking issue in rust-lang/rust.
     pub tracking: Option<IssueId>,
+    /// Label in rust-lang/rust for listing related issues.
     pub issue_label: Option<String>,
     pub stabilized: Option<Stabilization>,
+    /// RFC section describing the item as unresolved, in the same form as `rfc`.
+    #[serde(default)]
+    #[schemars(schema_with = "rfc_schema")]
     pub unresolved: Option<String>,
+    /// Link to show instead of the stabilization state.
     pub link: Option<Link>,
+    /// Sub-items this item depends on.
     #[serde(default)]
     pub deps: Vec<Item>,
+    /// Draft items are not shown on the site, `discover` adds new items
+    /// as drafts for review.
+    #[serde(default)]
+    pub draft: bool,
+}
+
+/// Schema of RFC references, which can be a bare number, since YAML reads
+/// numbers into strings as well.
+fn rfc_schema(_: &mut SchemaGenerator) -> Schema {
+    SchemaObject {
+        instance_type: Some(
+            vec![
+                InstanceType::String,
+                InstanceType::Integer,
+                InstanceType::Null,
+            ]
+            .into(),
+        ),
+        // Only applies to strings.
+        string: Some(Box::new(StringValidation {
+            pattern: Some(r"^[0-9]+(-[^#]+(#.+)?)?$".to_string()),
+            ..Default::default()
+        })),
+        number: Some(Box::new(NumberValidation {
+            minimum: Some(0.0),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct Stabilization {
-    pub version: String,
+    /// Rust version in the form of `1.xx`, omitted until the pull request
+    /// is merged, and the item is not shown as stabilized without it.
+    #[schemars(regex(pattern = r"^[0-9]+\.[0-9]+$"))]
+    pub version: Option<String>,
+    /// Stabilization pull request in rust-lang/rust.
     pub pr: IssueId,
 }
 
+impl Item {
+    pub fn id(&self) -> String {
+        match &self.id {
+            Some(id) => id.clone(),
+            None => slugify(&self.title),
+        }
+    }
+}
+
 impl InputData {
     pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
         let file = File::open(path)?;
         let data = serde_yaml::from_reader(file).context("failed to read yaml")?;
-        Ok(InputData(data))
+        InputData::from_data(data)
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let data = serde_yaml::from_str(text).context("failed to read yaml")?;
+        InputData::from_data(data)
+    }
+
+    fn from_data(data: BTreeMap<String, Vec<Item>>) -> Result<Self> {
+        let data = InputData(data);
+        data.check_ids()?;
+        Ok(data)
+    }
+
+    fn check_ids(&self) -> Result<()> {
+        let mut ids = HashSet::new();
+        for item in self.all_items() {
+            let id = item.id();
+            if !ids.insert(id) {
+                bail!("duplicate item id `{}`, specify `id` for it", item.id());
+            }
+        }
+        Ok(())
+    }
+
+    /// List all items including deps, parents before their deps.
+    pub fn all_items(&self) -> Vec<&Item> {
+        let mut result = Vec::new();
+        let mut items: Vec<_> = self.0.values().flatten().rev().collect();
+        while let Some(item) = items.pop() {
+            result.push(item);
+            items.extend(item.deps.iter().rev());
+        }
+        result
+    }
+
+    /// Get ids of all items including deps.
+    pub fn ids(&self) -> HashSet<String> {
+        self.all_items().into_iter().map(Item::id).collect()
     }
 
     pub fn get_fetch_list(&self) -> FetchList<'_> {
@@ -43,21 +143,43 @@ impl InputData {
             .for_each(|item| fetch_list.fill_from_item(item));
         fetch_list
     }
+
+    /// Get fetch lists of every item including deps, each of which only
+    /// covers the item itself.
+    pub fn get_item_fetch_lists(&self) -> Vec<(String, FetchList<'_>)> {
+        self.all_items()
+            .into_iter()
+            .map(|item| {
+                let mut fetch_list = FetchList::default();
+                fetch_list.fill_from_item_only(item);
+                (item.id(), fetch_list)
+            })
+            .collect()
+    }
 }
 
 #[derive(Default)]
 pub struct FetchList<'a> {
     pub labels: Vec<(Repo, &'a str)>,
     pub issues: Vec<(Repo, IssueId)>,
+    /// Tracking issues, whose body and linked pull requests are needed
+    /// besides the issue itself.
+    pub tracking: Vec<(Repo, IssueId)>,
 }
 
 impl<'a> FetchList<'a> {
     fn fill_from_item(&mut self, item: &'a Item) {
+        self.fill_from_item_only(item);
+        item.deps.iter().for_each(|dep| self.fill_from_item(dep));
+    }
+
+    fn fill_from_item_only(&mut self, item: &'a Item) {
         if let Some(rfc) = &item.rfc {
             self.issues.push((RFC_REPO.clone(), parse_rfc_for_id(rfc)));
         }
         if let Some(tracking) = &item.tracking {
             self.issues.push((RUSTC_REPO.clone(), *tracking));
+            self.tracking.push((RUSTC_REPO.clone(), *tracking));
         }
         if let Some(label) = &item.issue_label {
             self.labels.push((RUSTC_REPO.clone(), label.as_str()));
@@ -69,11 +191,72 @@ impl<'a> FetchList<'a> {
             self.issues
                 .push((RFC_REPO.clone(), parse_rfc_for_id(unresolved)));
         }
-        item.deps.iter().for_each(|dep| self.fill_from_item(dep));
     }
 }
 
+pub fn slugify(title: &str) -> String {
+    title
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_ascii_lowercase())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
 fn parse_rfc_for_id(rfc: &str) -> IssueId {
     let dash = rfc.find('-').unwrap_or(rfc.len());
     rfc[..dash].parse().expect("unexpected rfc number")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: &str = "
+A:
+  - title: First
+    tracking: 1
+    deps:
+      - title: Dep
+        issue_label: A-dep
+        deps:
+          - title: Nested
+  - title: Second
+    id: second-item
+    stabilized:
+      version: '1.39'
+      pr: 2
+B:
+  - title: Third
+";
+
+    #[test]
+    fn all_items_in_order() {
+        let data = InputData::parse(DATA).unwrap();
+        let ids: Vec<_> = data.all_items().into_iter().map(Item::id).collect();
+        assert_eq!(ids, ["first", "dep", "nested", "second-item", "third"]);
+        let expected: HashSet<_> = ids.into_iter().collect();
+        assert_eq!(data.ids(), expected);
+        let lists = data.get_item_fetch_lists();
+        let ids: HashSet<_> = lists.iter().map(|(id, _)| id.clone()).collect();
+        assert_eq!(ids, expected);
+        let (_, dep) = lists.iter().find(|(id, _)| id == "dep").unwrap();
+        assert_eq!(dep.labels, [(RUSTC_REPO.clone(), "A-dep")]);
+        assert!(dep.issues.is_empty());
+    }
+
+    #[test]
+    fn rfc_number() {
+        let data = InputData::parse("A:\n  - title: Item\n    rfc: 2362\n").unwrap();
+        assert_eq!(data.0["A"][0].rfc.as_deref(), Some("2362"));
+        let lists = data.get_item_fetch_lists();
+        assert_eq!(lists[0].1.issues, [(RFC_REPO.clone(), 2362)]);
+    }
+
+    #[test]
+    fn duplicate_ids() {
+        let data = "A:\n  - title: Item\n    deps:\n      - title: Other\n        id: item\n";
+        let error = InputData::parse(data).err().unwrap();
+        assert!(error.to_string().contains("`item`"), "{}", error);
+    }
+}
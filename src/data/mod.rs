@@ -1,18 +1,52 @@
+use crate::query::Repo;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+pub mod edit;
 pub mod input;
 pub mod output;
+pub mod questions;
+pub mod tasks;
 
 pub type IssueId = u32;
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Issue {
     pub number: u32,
     pub title: String,
     pub open: bool,
+    /// Merge state of a pull request, `None` for issues.
+    pub pull_request: Option<PullRequest>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PullRequest {
+    pub merged: bool,
+    /// Title of the milestone, which is the release version for rust-lang/rust.
+    pub milestone: Option<String>,
+}
+
+/// Pull request mentioning a tracking issue.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct LinkedPr {
+    pub repo: Repo,
+    pub number: IssueId,
+    pub title: String,
+    pub state: PrState,
+    pub merged_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PrState {
+    Open,
+    Merged,
+    Closed,
+}
+
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct Link {
     pub text: String,
     pub url: String,
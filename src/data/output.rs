@@ -1,19 +1,34 @@
 use super::input::{InputData, Item as InputItem};
-use super::{Issue, IssueId, Link};
+use super::questions::{self, Question, Source};
+use super::tasks::Progress;
+use super::{Issue, IssueId, Link, LinkedPr};
 use crate::fetcher::IssueData;
 use crate::query::Repo;
 use crate::{RFC_REPO, RUSTC_REPO};
+use chrono::{Duration, NaiveDate};
+use log::warn;
 use semver::{BuildMetadata, Prerelease, Version};
-use serde::Serialize;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
 
-pub struct OutputData(pub HashMap<String, Vec<Item>>);
+pub struct OutputData(pub BTreeMap<String, Vec<Item>>);
 
 impl OutputData {
-    pub fn from_input(input: InputData, issue_data: &IssueData, latest_stable: &Version) -> Self {
+    /// Build the output from the input and fetched data. Unresolved questions
+    /// are also read from RFC texts when a checkout of rust-lang/rfcs is given.
+    pub fn from_input(
+        input: InputData,
+        issue_data: &IssueData,
+        latest_stable: &Version,
+        rfcs_dir: Option<&Path>,
+    ) -> Self {
         let builder = Builder {
             issue_data,
             latest_stable,
+            rfcs_dir,
         };
         builder.build(input)
     }
@@ -21,9 +36,16 @@ impl OutputData {
 
 #[derive(Debug, Serialize)]
 pub struct Item {
+    pub id: String,
     pub title: String,
     pub rfc: Option<Rfc>,
     pub tracking: Option<Issue>,
+    /// Progress of the task list in the tracking issue.
+    pub progress: Option<Progress>,
+    /// Unresolved questions from the tracking issue and the RFC text.
+    pub questions: Vec<Question>,
+    /// Pull requests mentioning the tracking issue.
+    pub linked_prs: Vec<LinkedPr>,
     pub issue_label: Option<String>,
     pub issues: Vec<Issue>,
     pub stabilized: Option<Stabilization>,
@@ -32,21 +54,45 @@ pub struct Item {
     pub deps: Vec<Item>,
 }
 
+#[cfg(test)]
+impl Item {
+    /// Create an item with nothing but an id and a title.
+    pub fn new_for_test(id: &str, title: &str) -> Self {
+        Item {
+            id: id.to_string(),
+            title: title.to_string(),
+            rfc: None,
+            tracking: None,
+            progress: None,
+            questions: Vec::new(),
+            linked_prs: Vec::new(),
+            issue_label: None,
+            issues: Vec::new(),
+            stabilized: None,
+            unresolved: None,
+            link: None,
+            deps: Vec::new(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct Rfc {
-    issue: Issue,
-    url: String,
-    merged: bool,
+    pub issue: Issue,
+    pub url: String,
+    pub merged: bool,
 }
 
 #[derive(Debug, Serialize)]
 pub struct Stabilization {
     pub state: VersionState,
     pub version: String,
+    /// Release date of the version, `None` if it's not a 1.x release.
+    pub release_date: Option<NaiveDate>,
     pub pr: Issue,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum VersionState {
     Stable,
@@ -54,9 +100,20 @@ pub enum VersionState {
     Nightly,
 }
 
+impl VersionState {
+    pub fn name(self) -> &'static str {
+        match self {
+            VersionState::Stable => "stable",
+            VersionState::Beta => "beta",
+            VersionState::Nightly => "nightly",
+        }
+    }
+}
+
 struct Builder<'a> {
     issue_data: &'a IssueData,
     latest_stable: &'a Version,
+    rfcs_dir: Option<&'a Path>,
 }
 
 impl Builder<'_> {
@@ -72,15 +129,28 @@ impl Builder<'_> {
     fn convert_items(&self, items: Vec<InputItem>) -> Vec<Item> {
         items
             .into_iter()
+            .filter(|item| !item.draft)
             .map(|item| self.convert_item(item))
             .collect()
     }
 
     fn convert_item(&self, item: InputItem) -> Item {
+        let questions = self.get_questions(&item);
         Item {
+            id: item.id(),
             title: item.title,
             rfc: self.convert_rfc(item.rfc),
             tracking: self.get_optional_issue(&RUSTC_REPO, item.tracking),
+            progress: item.tracking.and_then(|id| {
+                let body = self.issue_data.bodies.get(&(RUSTC_REPO.clone(), id))?;
+                Progress::from_body(body)
+            }),
+            questions,
+            linked_prs: item
+                .tracking
+                .and_then(|id| self.issue_data.linked_prs.get(&(RUSTC_REPO.clone(), id)))
+                .cloned()
+                .unwrap_or_default(),
             issues: item
                 .issue_label
                 .as_ref()
@@ -94,10 +164,14 @@ impl Builder<'_> {
                 })
                 .unwrap_or_default(),
             issue_label: item.issue_label,
-            stabilized: item.stabilized.map(|stabilized| Stabilization {
-                state: self.get_version_state(&stabilized.version),
-                version: stabilized.version,
-                pr: self.get_issue(&RUSTC_REPO, stabilized.pr),
+            stabilized: item.stabilized.and_then(|stabilized| {
+                let version = stabilized.version?;
+                Some(Stabilization {
+                    state: version_state(self.latest_stable, &version),
+                    release_date: release_date(&version),
+                    version,
+                    pr: self.get_issue(&RUSTC_REPO, stabilized.pr),
+                })
             }),
             unresolved: self.convert_rfc(item.unresolved),
             link: item.link,
@@ -105,21 +179,21 @@ impl Builder<'_> {
         }
     }
 
-    fn get_version_state(&self, version: &str) -> VersionState {
-        let version =
-            Version::parse(&format!("{}.0", version)).expect("invalid stabilization version");
-        if *self.latest_stable >= version {
-            return VersionState::Stable;
+    fn get_questions(&self, item: &InputItem) -> Vec<Question> {
+        let mut result = Vec::new();
+        if let Some(tracking) = item.tracking {
+            if let Some(body) = self.issue_data.bodies.get(&(RUSTC_REPO.clone(), tracking)) {
+                result.extend(questions::parse_questions(body, Source::Tracking));
+            }
         }
-        let mut beta = self.latest_stable.clone();
-        beta.minor += 1;
-        beta.patch = 0;
-        beta.pre = Prerelease::EMPTY;
-        beta.build = BuildMetadata::EMPTY;
-        if beta >= version {
-            return VersionState::Beta;
+        if let (Some(dir), Some(rfc)) = (self.rfcs_dir, &item.rfc) {
+            match read_rfc_text(dir, rfc) {
+                Ok(Some(text)) => result.extend(questions::parse_questions(&text, Source::Rfc)),
+                Ok(None) => {}
+                Err(e) => warn!("failed to read text of RFC {}: {}", rfc, e),
+            }
         }
-        VersionState::Nightly
+        result
     }
 
     fn convert_rfc(&self, rfc: Option<String>) -> Option<Rfc> {
@@ -154,3 +228,85 @@ impl Builder<'_> {
         self.issue_data.issues[&(repo.clone(), id)].clone()
     }
 }
+
+fn parse_version(version: &str) -> Version {
+    Version::parse(&format!("{}.0", version)).expect("invalid stabilization version")
+}
+
+/// Get the state of the given Rust version, given the latest stable release.
+pub fn version_state(latest_stable: &Version, version: &str) -> VersionState {
+    let version = parse_version(version);
+    if *latest_stable >= version {
+        return VersionState::Stable;
+    }
+    let mut beta = latest_stable.clone();
+    beta.minor += 1;
+    beta.patch = 0;
+    beta.pre = Prerelease::EMPTY;
+    beta.build = BuildMetadata::EMPTY;
+    if beta >= version {
+        return VersionState::Beta;
+    }
+    VersionState::Nightly
+}
+
+/// Get the release date of the given Rust version, or `None` if it's not
+/// a valid 1.x version, whose release dates are unknown.
+///
+/// Rust has been released every six weeks since 1.1.
+pub fn release_date(version: &str) -> Option<NaiveDate> {
+    let version = Version::parse(&format!("{}.0", version)).ok()?;
+    if version.major != 1 {
+        return None;
+    }
+    if version.minor == 0 {
+        return NaiveDate::from_ymd_opt(2015, 5, 15);
+    }
+    let first_release = NaiveDate::from_ymd_opt(2015, 6, 25).unwrap();
+    Some(first_release + Duration::weeks(6 * (version.minor as i64 - 1)))
+}
+
+/// Read the text of the given RFC from a checkout of rust-lang/rfcs,
+/// or `None` if it is not merged.
+fn read_rfc_text(rfcs_dir: &Path, rfc: &str) -> io::Result<Option<String>> {
+    let page = rfc.split('#').next().unwrap();
+    let text_dir = rfcs_dir.join("text");
+    let path = if page.contains('-') {
+        text_dir.join(format!("{}.md", page))
+    } else {
+        let prefix = format!("{:0>4}-", page);
+        let entry = fs::read_dir(&text_dir)?
+            .filter_map(Result::ok)
+            .find(|entry| entry.file_name().to_string_lossy().starts_with(&prefix));
+        match entry {
+            Some(entry) => entry.path(),
+            None => return Ok(None),
+        }
+    };
+    fs::read_to_string(path).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn release_dates() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d);
+        assert_eq!(release_date("1.0"), date(2015, 5, 15));
+        assert_eq!(release_date("1.1"), date(2015, 6, 25));
+        assert_eq!(release_date("1.39"), date(2019, 11, 7));
+        assert_eq!(release_date("2.0"), None);
+        assert_eq!(release_date("01.5"), None);
+    }
+
+    #[test]
+    fn version_states() {
+        let latest = Version::new(1, 40, 0);
+        assert_eq!(version_state(&latest, "1.39"), VersionState::Stable);
+        assert_eq!(version_state(&latest, "1.40"), VersionState::Stable);
+        assert_eq!(version_state(&latest, "1.41"), VersionState::Beta);
+        assert_eq!(version_state(&latest, "1.42"), VersionState::Nightly);
+        assert_eq!(version_state(&latest, "2.0"), VersionState::Nightly);
+    }
+}
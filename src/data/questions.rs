@@ -0,0 +1,284 @@
+//! Unresolved questions listed in tracking issues and RFCs.
+
+use super::tasks::{self, TaskRef};
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct Question {
+    /// Text of the question with Markdown links replaced by their text.
+    pub text: String,
+    /// State of the checkbox, `None` for plain list items.
+    pub checked: Option<bool>,
+    /// Issues and pull requests referenced by the question.
+    pub refs: Vec<TaskRef>,
+    pub source: Source,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    Tracking,
+    Rfc,
+}
+
+/// Parse the list items in the "Unresolved questions" section of the given
+/// Markdown, which is either a heading or a line in bold.
+pub fn parse_questions(text: &str, source: Source) -> Vec<Question> {
+    let mut result = Vec::new();
+    // Heading level of the section, where 0 means a bold line.
+    let mut section = None;
+    // Indentation of top-level list items in the section.
+    let mut list_indent = None;
+    let mut in_code = false;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        if let Some((level, title)) = parse_heading(trimmed) {
+            if title.to_ascii_lowercase().contains("unresolved question") {
+                section = Some(level);
+                list_indent = None;
+                continue;
+            }
+            // A section starting with a heading ends at a heading of the same
+            // or a higher level. One starting with a bold line ends at any
+            // heading, or at a bold line after its list, since bold lines
+            // before that are more likely to be part of the section.
+            let ends = match section {
+                Some(0) => level > 0 || list_indent.is_some(),
+                Some(section) => level > 0 && level <= section,
+                None => false,
+            };
+            if ends {
+                section = None;
+            }
+            continue;
+        }
+        if section.is_none() {
+            continue;
+        }
+        let item = match strip_list_marker(trimmed) {
+            Some(item) => item,
+            None => continue,
+        };
+        // Only top-level list items are questions, nested ones are details.
+        let indent = line.len() - line.trim_start().len();
+        match list_indent {
+            Some(list_indent) if indent > list_indent => continue,
+            _ => list_indent = Some(indent),
+        }
+        if let Some(task) = tasks::parse_task(trimmed) {
+            result.push(Question {
+                text: task.text,
+                checked: Some(task.done),
+                refs: task.refs,
+                source,
+            });
+        } else {
+            result.push(Question {
+                text: tasks::strip_links(item.trim()),
+                checked: None,
+                refs: tasks::find_refs(item),
+                source,
+            });
+        }
+    }
+    result
+}
+
+fn strip_list_marker(line: &str) -> Option<&str> {
+    ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))
+}
+
+/// Parse a Markdown heading or a line in bold into its level and text.
+///
+/// Headings without a space after `#` are accepted too, since they are
+/// common in issue bodies, as long as the text starts with a letter unlike
+/// references like `#5`.
+fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if level > 0 {
+        let title = &line[level..];
+        let is_heading = match title.chars().next() {
+            None => true,
+            Some(c) => c == ' ' || c.is_alphabetic(),
+        };
+        if !is_heading {
+            return None;
+        }
+        return Some((level, title.trim().trim_end_matches('#').trim_end()));
+    }
+    let title = line.strip_prefix("**")?.strip_suffix("**")?;
+    Some((0, title.trim().trim_end_matches(':')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(questions: &[Question]) -> Vec<&str> {
+        questions.iter().map(|q| q.text.as_str()).collect()
+    }
+
+    #[test]
+    fn tracking_issue_with_bold_lines() {
+        let body = "\
+This is a tracking issue for the RFC \"async/await\" (rust-lang/rfcs#2394).
+
+**Steps:**
+- [x] Implement the RFC (#51580)
+- [ ] Stabilization PR ([see instructions on forge][stabilization-guide])
+
+**Unresolved questions:**
+- [ ] Final syntax for `await` (#57640)
+  - [ ] Prefix or postfix, see [this comment](https://github.com/rust-lang/rust/issues/50547)
+- [x] Should `async fn` be allowed in traits? #91611
+- Lifetime elision in `async fn`
+
+**Implementation history:**
+- #51580
+";
+        let questions = parse_questions(body, Source::Tracking);
+        assert_eq!(
+            texts(&questions),
+            [
+                "Final syntax for `await` (#57640)",
+                "Should `async fn` be allowed in traits? #91611",
+                "Lifetime elision in `async fn`",
+            ]
+        );
+        let checked: Vec<_> = questions.iter().map(|q| q.checked).collect();
+        assert_eq!(checked, [Some(false), Some(true), None]);
+        assert_eq!(questions[0].refs[0].text, "#57640");
+    }
+
+    #[test]
+    fn tracking_issue_with_headings() {
+        let body = "\
+The feature gate for the issue is `#![feature(async_closure)]`.
+
+### About tracking issues
+
+Tracking issues are used to record the overall progress of implementation.
+
+### Steps
+- [ ] Implement the RFC
+
+### Unresolved Questions
+XXX --- list all the \"unresolved questions\" found in the RFC to ensure they are
+not forgotten
+
+#### Closures
+- [ ] Should `async` closures capture by reference?
+
+### Implementation history
+- #62292
+";
+        let questions = parse_questions(body, Source::Tracking);
+        // Lower level headings are part of the section.
+        assert_eq!(
+            texts(&questions),
+            ["Should `async` closures capture by reference?"]
+        );
+    }
+
+    #[test]
+    fn rfc_text() {
+        let text = "\
+# Drawbacks
+[drawbacks]: #drawbacks
+
+- Not a question
+
+# Unresolved questions
+[unresolved-questions]: #unresolved-questions
+
+The final syntax for the `await` expression is left open:
+
+* Should it be a prefix keyword?
+* Should it be postfix, like `.await`?
+    * With parentheses?
+
+```rust
+// - not a question either
+```
+
+## Try blocks
+
+* How does `?` interact with `async` blocks?
+
+# Future possibilities
+[future-possibilities]: #future-possibilities
+
+* Async iterators
+";
+        let questions = parse_questions(text, Source::Rfc);
+        assert_eq!(
+            texts(&questions),
+            [
+                "Should it be a prefix keyword?",
+                "Should it be postfix, like `.await`?",
+                "How does `?` interact with `async` blocks?",
+            ]
+        );
+        assert!(questions.iter().all(|q| q.checked.is_none()));
+    }
+
+    #[test]
+    fn bold_paragraph_in_bold_section() {
+        let body = "\
+**Unresolved questions**
+
+**These need to be resolved before stabilization.**
+
+- [ ] First
+- [ ] Second
+
+**Implementation history**
+- [ ] Not a question
+";
+        let questions = parse_questions(body, Source::Tracking);
+        assert_eq!(texts(&questions), ["First", "Second"]);
+    }
+
+    #[test]
+    fn indented_list() {
+        let body = "\
+## Unresolved questions
+   - First
+      - Detail
+   - Second
+";
+        let questions = parse_questions(body, Source::Tracking);
+        assert_eq!(texts(&questions), ["First", "Second"]);
+    }
+
+    #[test]
+    fn headings() {
+        assert_eq!(
+            parse_heading("## Unresolved questions"),
+            Some((2, "Unresolved questions"))
+        );
+        assert_eq!(
+            parse_heading("##Unresolved questions"),
+            Some((2, "Unresolved questions"))
+        );
+        assert_eq!(parse_heading("# Title #"), Some((1, "Title")));
+        assert_eq!(parse_heading("**Steps:**"), Some((0, "Steps")));
+        assert_eq!(parse_heading("#5 is related"), None);
+        assert_eq!(parse_heading("#![feature(async_closure)]"), None);
+        assert_eq!(parse_heading("Plain text"), None);
+    }
+
+    #[test]
+    fn no_section() {
+        assert!(parse_questions("- [ ] Task\n\n## Steps\n- Step\n", Source::Tracking).is_empty());
+    }
+}
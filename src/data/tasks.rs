@@ -0,0 +1,269 @@
+//! Task lists in bodies of tracking issues.
+//!
+//! Tracking issues in rust-lang/rust list their steps as Markdown task list
+//! items like `- [x] Implement`, which tell more about the progress than
+//! whether the issue is open.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+
+static RE_MARKDOWN_LINK: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[([^\]]*)\]\([^)]*\)").unwrap());
+static RE_URL_REF: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"https://github\.com/([\w.-]+)/([\w.-]+)/(?:issues|pull)/(\d+)").unwrap()
+});
+static RE_SHORT_REF: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?:^|[^\w/&])(?:([\w.-]+)/([\w.-]+))?#(\d+)\b").unwrap());
+
+#[derive(Debug, Serialize)]
+pub struct Progress {
+    pub done: usize,
+    pub total: usize,
+    /// Fraction of tasks done, from 0 to 1.
+    pub fraction: f64,
+    /// Tasks not done yet, in the order they appear in the issue.
+    pub remaining: Vec<Task>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Task {
+    pub done: bool,
+    /// Text of the task with Markdown links replaced by their text.
+    pub text: String,
+    /// Issues and pull requests referenced by the task.
+    pub refs: Vec<TaskRef>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct TaskRef {
+    /// Short form like `#123`, with the repository unless it's rust-lang/rust.
+    pub text: String,
+    pub url: String,
+}
+
+impl Progress {
+    /// Summarize the task list of the given issue body, if it has any.
+    pub fn from_body(body: &str) -> Option<Self> {
+        let tasks = parse_tasks(body);
+        if tasks.is_empty() {
+            return None;
+        }
+        let total = tasks.len();
+        let done = tasks.iter().filter(|task| task.done).count();
+        Some(Progress {
+            done,
+            total,
+            fraction: done as f64 / total as f64,
+            remaining: tasks.into_iter().filter(|task| !task.done).collect(),
+        })
+    }
+}
+
+/// Parse all task list items in the given Markdown, skipping code blocks.
+fn parse_tasks(body: &str) -> Vec<Task> {
+    let mut result = Vec::new();
+    let mut in_code = false;
+    for line in body.lines() {
+        let line = line.trim();
+        if line.starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        if let Some(task) = parse_task(line) {
+            result.push(task);
+        }
+    }
+    result
+}
+
+/// Parse a line like `- [x] text` into a task.
+pub fn parse_task(line: &str) -> Option<Task> {
+    let rest = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))?
+        .trim_start();
+    let (done, text) = if let Some(text) = rest.strip_prefix("[ ]") {
+        (false, text)
+    } else if let Some(text) = rest
+        .strip_prefix("[x]")
+        .or_else(|| rest.strip_prefix("[X]"))
+    {
+        (true, text)
+    } else {
+        return None;
+    };
+    Some(Task {
+        done,
+        text: strip_links(text.trim()),
+        refs: find_refs(text),
+    })
+}
+
+/// Replace Markdown links in the text with their text.
+pub fn strip_links(text: &str) -> String {
+    RE_MARKDOWN_LINK.replace_all(text, "$1").into_owned()
+}
+
+/// Find references to issues and pull requests, either as URLs or in the
+/// short form, where `#123` refers to rust-lang/rust.
+pub fn find_refs(text: &str) -> Vec<TaskRef> {
+    let mut result = Vec::new();
+    let urls = RE_URL_REF.captures_iter(text).map(|caps| {
+        let start = caps.get(0).unwrap().start();
+        (
+            start,
+            caps[1].to_string(),
+            caps[2].to_string(),
+            caps[3].to_string(),
+        )
+    });
+    // Blank out URLs keeping the offsets, so that they are not matched again.
+    let without_urls =
+        RE_URL_REF.replace_all(text, |caps: &regex::Captures| " ".repeat(caps[0].len()));
+    let shorts = RE_SHORT_REF.captures_iter(&without_urls).map(|caps| {
+        let start = caps.get(0).unwrap().start();
+        let owner = caps.get(1).map_or("rust-lang", |m| m.as_str());
+        let repo = caps.get(2).map_or("rust", |m| m.as_str());
+        (
+            start,
+            owner.to_string(),
+            repo.to_string(),
+            caps[3].to_string(),
+        )
+    });
+    let mut refs: Vec<_> = urls.chain(shorts).collect();
+    refs.sort_by_key(|(start, ..)| *start);
+    for (_, owner, repo, number) in refs {
+        let task_ref = TaskRef {
+            text: if owner == "rust-lang" && repo == "rust" {
+                format!("#{}", number)
+            } else {
+                format!("{}/{}#{}", owner, repo, number)
+            },
+            url: format!("https://github.com/{}/{}/issues/{}", owner, repo, number),
+        };
+        if !result.contains(&task_ref) {
+            result.push(task_ref);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ref_texts(refs: &[TaskRef]) -> Vec<&str> {
+        refs.iter().map(|r| r.text.as_str()).collect()
+    }
+
+    #[test]
+    fn task_markers() {
+        for line in ["- [x] Done", "- [X] Done", "* [x] Done", "+ [x] Done"] {
+            let task = parse_task(line).unwrap();
+            assert!(task.done, "{}", line);
+            assert_eq!(task.text, "Done");
+        }
+        let task = parse_task("- [ ] Not yet").unwrap();
+        assert!(!task.done);
+        assert_eq!(task.text, "Not yet");
+    }
+
+    #[test]
+    fn not_tasks() {
+        assert!(parse_task("- plain item").is_none());
+        assert!(parse_task("[x] no marker").is_none());
+        assert!(parse_task("-[x] no space").is_none());
+        assert!(parse_task("- [y] unknown state").is_none());
+    }
+
+    #[test]
+    fn skip_code_blocks() {
+        let body = "\
+Steps:
+- [x] Implement
+```markdown
+- [ ] Example in code
+```
+- [ ] Stabilize
+";
+        let tasks = parse_tasks(body);
+        let texts: Vec<_> = tasks.iter().map(|task| task.text.as_str()).collect();
+        assert_eq!(texts, ["Implement", "Stabilize"]);
+    }
+
+    #[test]
+    fn progress() {
+        let progress = Progress::from_body("- [x] a\n- [ ] b\n- [x] c\n- [ ] d").unwrap();
+        assert_eq!(progress.done, 2);
+        assert_eq!(progress.total, 4);
+        assert_eq!(progress.fraction, 0.5);
+        let remaining: Vec<_> = progress.remaining.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(remaining, ["b", "d"]);
+        assert!(Progress::from_body("No tasks here.").is_none());
+    }
+
+    #[test]
+    fn strip_markdown_links() {
+        assert_eq!(
+            strip_links("Implement [the RFC](https://github.com/rust-lang/rfcs/pull/2394)"),
+            "Implement the RFC",
+        );
+    }
+
+    #[test]
+    fn short_refs() {
+        let refs = find_refs("Fix #123 and rust-lang/futures-rs#45");
+        assert_eq!(ref_texts(&refs), ["#123", "rust-lang/futures-rs#45"]);
+        assert_eq!(refs[0].url, "https://github.com/rust-lang/rust/issues/123");
+        assert_eq!(
+            refs[1].url,
+            "https://github.com/rust-lang/futures-rs/issues/45"
+        );
+    }
+
+    #[test]
+    fn url_refs() {
+        let refs = find_refs(
+            "See https://github.com/rust-lang/rust/pull/51580 \
+             and https://github.com/tokio-rs/tokio/issues/7",
+        );
+        assert_eq!(ref_texts(&refs), ["#51580", "tokio-rs/tokio#7"]);
+        assert_eq!(refs[1].url, "https://github.com/tokio-rs/tokio/issues/7");
+    }
+
+    #[test]
+    fn url_and_short_refs_in_order() {
+        // The fragment of the URL must not be taken as a short reference.
+        let refs =
+            find_refs("#1 then https://github.com/rust-lang/rfcs/pull/2394#issuecomment-2 then #3");
+        assert_eq!(ref_texts(&refs), ["#1", "rust-lang/rfcs#2394", "#3"]);
+    }
+
+    #[test]
+    fn ignore_non_refs() {
+        assert!(find_refs("Use &#123; or foo#1 or a/b/c#2").is_empty());
+    }
+
+    #[test]
+    fn dedup_refs() {
+        let refs = find_refs(
+            "[#50547](https://github.com/rust-lang/rust/issues/50547), \
+             see #50547 and rust-lang/rust#50547",
+        );
+        assert_eq!(ref_texts(&refs), ["#50547"]);
+    }
+
+    #[test]
+    fn task_refs() {
+        let task = parse_task(
+            "- [ ] Stabilize ([#62290](https://github.com/rust-lang/rust/issues/62290))",
+        )
+        .unwrap();
+        assert_eq!(task.text, "Stabilize (#62290)");
+        assert_eq!(ref_texts(&task.refs), ["#62290"]);
+    }
+}
@@ -0,0 +1,437 @@
+//! Differences between two caches or two history snapshots.
+
+use crate::cache;
+use crate::changes::StabilizationState;
+use crate::data::input::InputData;
+use crate::data::output::version_state;
+use crate::data::{Issue, IssueId};
+use crate::fetcher::IssueData;
+use crate::history::{self, ItemState, LabelCounts, Snapshot};
+use crate::{DATA_FILE, HISTORY_FILE, RUSTC_REPO};
+use anyhow::{Context, Result};
+use semver::Version;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::path::Path;
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Default, Serialize)]
+pub struct Diff {
+    opened: Vec<IssueRef>,
+    closed: Vec<IssueRef>,
+    retitled: Vec<Retitle>,
+    label_added: Vec<LabelMember>,
+    label_removed: Vec<LabelMember>,
+    /// Changes of issue counts, only available for snapshots.
+    label_counts: Vec<LabelCountChange>,
+    /// Changes of item states, available for caches which both record the
+    /// latest tag.
+    items: Vec<ItemChange>,
+    /// Kinds of changes which can't be compared.
+    notes: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct IssueRef {
+    repo: String,
+    number: IssueId,
+    title: String,
+}
+
+#[derive(Serialize)]
+struct Retitle {
+    repo: String,
+    number: IssueId,
+    from: String,
+    to: String,
+}
+
+#[derive(Serialize)]
+struct LabelMember {
+    repo: String,
+    label: String,
+    number: IssueId,
+    title: Option<String>,
+}
+
+#[derive(Serialize)]
+struct LabelCountChange {
+    label: String,
+    from: Option<LabelCounts>,
+    to: Option<LabelCounts>,
+}
+
+#[derive(Serialize)]
+struct ItemChange {
+    id: String,
+    from: Option<StabilizationState>,
+    to: Option<StabilizationState>,
+}
+
+/// Compare two caches given as paths, or two history snapshots given as ids.
+pub fn run(old: &str, new: &str, format: Format) -> Result<()> {
+    let diff = match (old.parse::<u32>(), new.parse::<u32>()) {
+        (Ok(old), Ok(new)) => {
+            let snapshots = history::load(HISTORY_FILE).context("failed to read history")?;
+            let find = |id| {
+                let snapshot = snapshots.iter().find(|snapshot| snapshot.id == id);
+                snapshot.with_context(|| format!("snapshot {} doesn't exist", id))
+            };
+            Diff::from_snapshots(find(old)?, find(new)?)
+        }
+        _ => {
+            let old = load_cache(Path::new(old))?;
+            let new = load_cache(Path::new(new))?;
+            let input_data =
+                InputData::from_file(DATA_FILE).context("failed to read input data")?;
+            Diff::from_caches(&old, &new, &input_data)
+        }
+    };
+    match format {
+        Format::Text => print!("{}", diff.to_text()),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+    }
+    Ok(())
+}
+
+fn load_cache(path: &Path) -> Result<IssueData> {
+    cache::load_path(path).with_context(|| format!("failed to read cache {}", path.display()))
+}
+
+impl Diff {
+    fn from_caches(old: &IssueData, new: &IssueData, input_data: &InputData) -> Self {
+        let mut diff = Diff::default();
+        for ((repo, number), new_issue) in new.issues.iter() {
+            let old_issue = match old.issues.get(&(repo.clone(), *number)) {
+                Some(issue) => issue,
+                // New issues are only interesting when they are open.
+                None if new_issue.open => {
+                    diff.opened.push(IssueRef {
+                        repo: repo.to_string(),
+                        number: *number,
+                        title: new_issue.title.clone(),
+                    });
+                    continue;
+                }
+                None => continue,
+            };
+            if old_issue.open != new_issue.open {
+                let list = if new_issue.open {
+                    &mut diff.opened
+                } else {
+                    &mut diff.closed
+                };
+                list.push(IssueRef {
+                    repo: repo.to_string(),
+                    number: *number,
+                    title: new_issue.title.clone(),
+                });
+            }
+            if old_issue.title != new_issue.title {
+                diff.retitled.push(Retitle {
+                    repo: repo.to_string(),
+                    number: *number,
+                    from: old_issue.title.clone(),
+                    to: new_issue.title.clone(),
+                });
+            }
+        }
+        let labels: BTreeSet<_> = old.labels.keys().chain(new.labels.keys()).collect();
+        for key in labels {
+            let (repo, label) = key;
+            let old_members: BTreeSet<_> = old.labels.get(key).into_iter().flatten().collect();
+            let new_members: BTreeSet<_> = new.labels.get(key).into_iter().flatten().collect();
+            let member = |number: IssueId, issue: Option<&Issue>| LabelMember {
+                repo: repo.to_string(),
+                label: label.clone(),
+                number,
+                title: issue.map(|issue| issue.title.clone()),
+            };
+            for number in new_members.difference(&old_members) {
+                let issue = new.issues.get(&(repo.clone(), **number));
+                diff.label_added.push(member(**number, issue));
+            }
+            for number in old_members.difference(&new_members) {
+                let issue = old.issues.get(&(repo.clone(), **number));
+                diff.label_removed.push(member(**number, issue));
+            }
+        }
+        match (item_states(input_data, old), item_states(input_data, new)) {
+            (Some(old), Some(new)) => diff.compare_items(&old, &new),
+            _ => diff.notes.push(
+                "item states are not compared, since a cache doesn't record the latest tag, \
+                 which is recorded on the next build"
+                    .to_string(),
+            ),
+        }
+        diff
+    }
+
+    fn from_snapshots(old: &Snapshot, new: &Snapshot) -> Self {
+        let mut diff = Diff::default();
+        diff.notes.push(
+            "snapshots only record issue counts, so issues opened, closed, retitled, \
+             or moved between labels are not listed"
+                .to_string(),
+        );
+        let labels: BTreeSet<_> = old.labels.keys().chain(new.labels.keys()).collect();
+        for label in labels {
+            let (from, to) = (old.labels.get(label), new.labels.get(label));
+            if from != to {
+                diff.label_counts.push(LabelCountChange {
+                    label: label.clone(),
+                    from: from.copied(),
+                    to: to.copied(),
+                });
+            }
+        }
+        diff.compare_items(&old.items, &new.items);
+        diff
+    }
+
+    fn compare_items(
+        &mut self,
+        old: &BTreeMap<String, ItemState>,
+        new: &BTreeMap<String, ItemState>,
+    ) {
+        let ids: BTreeSet<_> = old.keys().chain(new.keys()).collect();
+        for id in ids {
+            let from = old.get(id).and_then(|item| item.stabilized.clone());
+            let to = new.get(id).and_then(|item| item.stabilized.clone());
+            if from != to {
+                self.items.push(ItemChange {
+                    id: id.clone(),
+                    from,
+                    to,
+                });
+            }
+        }
+    }
+
+    fn to_text(&self) -> String {
+        let mut out = String::new();
+        for issue in self.opened.iter() {
+            writeln!(
+                out,
+                "opened {}#{}: {}",
+                issue.repo, issue.number, issue.title
+            )
+            .unwrap();
+        }
+        for issue in self.closed.iter() {
+            writeln!(
+                out,
+                "closed {}#{}: {}",
+                issue.repo, issue.number, issue.title
+            )
+            .unwrap();
+        }
+        for retitle in self.retitled.iter() {
+            writeln!(
+                out,
+                "retitled {}#{}: {:?} -> {:?}",
+                retitle.repo, retitle.number, retitle.from, retitle.to
+            )
+            .unwrap();
+        }
+        for (action, members) in [
+            ("added to", &self.label_added),
+            ("removed from", &self.label_removed),
+        ] {
+            for member in members {
+                write!(
+                    out,
+                    "{} {}: {}#{}",
+                    action, member.label, member.repo, member.number
+                )
+                .unwrap();
+                if let Some(title) = &member.title {
+                    write!(out, " {}", title).unwrap();
+                }
+                out.push('\n');
+            }
+        }
+        for change in self.label_counts.iter() {
+            let describe = |counts: &Option<LabelCounts>| match counts {
+                Some(counts) => format!("{} open / {} closed", counts.open, counts.closed),
+                None => "none".to_string(),
+            };
+            writeln!(
+                out,
+                "label {}: {} -> {}",
+                change.label,
+                describe(&change.from),
+                describe(&change.to)
+            )
+            .unwrap();
+        }
+        for change in self.items.iter() {
+            let describe = |state: &Option<StabilizationState>| match state {
+                Some(state) => format!("{} {}", state.state.name(), state.version),
+                None => "not stabilized".to_string(),
+            };
+            writeln!(
+                out,
+                "item {}: {} -> {}",
+                change.id,
+                describe(&change.from),
+                describe(&change.to)
+            )
+            .unwrap();
+        }
+        if out.is_empty() {
+            out.push_str("no changes\n");
+        }
+        for note in self.notes.iter() {
+            writeln!(out, "note: {}", note).unwrap();
+        }
+        out
+    }
+}
+
+/// Get states of items in data.yml with the given cache, or `None` if the
+/// cache doesn't record the latest tag, which stabilization states depend on.
+fn item_states(input_data: &InputData, data: &IssueData) -> Option<BTreeMap<String, ItemState>> {
+    let latest_stable = Version::parse(data.latest_tag.as_ref()?).ok()?;
+    let items = input_data
+        .all_items()
+        .into_iter()
+        .filter(|item| !item.draft);
+    let states = items.map(|item| {
+        let tracking = item
+            .tracking
+            .and_then(|number| data.issues.get(&(RUSTC_REPO.clone(), number)));
+        let state = ItemState {
+            tracking_open: tracking.map(|issue| issue.open),
+            stabilized: item.stabilized.as_ref().and_then(|stabilized| {
+                let version = stabilized.version.clone()?;
+                Some(StabilizationState {
+                    state: version_state(&latest_stable, &version),
+                    version,
+                })
+            }),
+        };
+        (item.id(), state)
+    });
+    Some(states.collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::output::VersionState;
+    use chrono::{TimeZone, Utc};
+
+    fn issue(number: IssueId, title: &str, open: bool) -> Issue {
+        Issue {
+            number,
+            title: title.to_string(),
+            open,
+            pull_request: None,
+        }
+    }
+
+    fn cache(issues: Vec<Issue>, label: Vec<IssueId>, latest_tag: Option<&str>) -> IssueData {
+        let repo = RUSTC_REPO.clone();
+        let mut data = IssueData::default();
+        for issue in issues {
+            data.issues.insert((repo.clone(), issue.number), issue);
+        }
+        data.labels.insert((repo, "A-a".to_string()), label);
+        data.latest_tag = latest_tag.map(str::to_string);
+        data
+    }
+
+    fn input() -> InputData {
+        InputData::parse(
+            "A:
+  - title: Item
+    tracking: 1
+    stabilized:
+      version: '1.40'
+      pr: 9
+",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn caches() {
+        let old = cache(
+            vec![
+                issue(1, "Tracking", true),
+                issue(2, "Old", true),
+                issue(3, "C", true),
+            ],
+            vec![2, 3],
+            Some("1.39.0"),
+        );
+        let new = cache(
+            vec![
+                issue(1, "Tracking", true),
+                issue(2, "New", false),
+                issue(3, "C", true),
+                issue(4, "D", true),
+                issue(5, "E", false),
+            ],
+            vec![3, 4],
+            Some("1.40.0"),
+        );
+        let diff = Diff::from_caches(&old, &new, &input());
+        assert_eq!(
+            diff.to_text(),
+            "opened rust-lang/rust#4: D\n\
+             closed rust-lang/rust#2: New\n\
+             retitled rust-lang/rust#2: \"Old\" -> \"New\"\n\
+             added to A-a: rust-lang/rust#4 D\n\
+             removed from A-a: rust-lang/rust#2 Old\n\
+             item item: beta 1.40 -> stable 1.40\n"
+        );
+    }
+
+    #[test]
+    fn caches_without_latest_tag() {
+        let old = cache(vec![issue(1, "Tracking", true)], vec![], None);
+        let new = cache(vec![issue(1, "Tracking", true)], vec![], Some("1.40.0"));
+        let diff = Diff::from_caches(&old, &new, &input());
+        let text = diff.to_text();
+        assert!(text.starts_with("no changes\nnote: item states are not compared"));
+        assert!(Diff::from_caches(&new, &new, &input()).notes.is_empty());
+    }
+
+    #[test]
+    fn snapshots() {
+        let snapshot = |id, open, stabilized: Option<&str>| Snapshot {
+            id,
+            time: Utc.with_ymd_and_hms(2020, 1, id, 0, 0, 0).unwrap(),
+            labels: BTreeMap::from([("A-a".to_string(), LabelCounts { open, closed: 0 })]),
+            items: BTreeMap::from([(
+                "item".to_string(),
+                ItemState {
+                    tracking_open: Some(true),
+                    stabilized: stabilized.map(|version| StabilizationState {
+                        version: version.to_string(),
+                        state: VersionState::Nightly,
+                    }),
+                },
+            )]),
+        };
+        let diff = Diff::from_snapshots(&snapshot(1, 2, None), &snapshot(2, 3, Some("1.41")));
+        let text = diff.to_text();
+        assert!(
+            text.starts_with(
+                "label A-a: 2 open / 0 closed -> 3 open / 0 closed\n\
+                 item item: not stabilized -> nightly 1.41\n\
+                 note: snapshots only record issue counts"
+            ),
+            "{}",
+            text
+        );
+    }
+}
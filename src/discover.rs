@@ -0,0 +1,70 @@
+//! Discovery of tracking issues which are not in data.yml yet.
+
+use crate::add;
+use crate::data::edit::Document;
+use crate::data::input::InputData;
+use crate::query::GitHubQuery;
+use crate::{DATA_FILE, RUSTC_REPO};
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+
+pub async fn run(query: &GitHubQuery<'_>, labels: &[String], section: Option<&str>) -> Result<()> {
+    let text = fs::read_to_string(DATA_FILE).context("failed to read data.yml")?;
+    let input_data = InputData::parse(&text).context("failed to read input data")?;
+    // Draft items count as referenced, so that they are not added again.
+    let known: HashSet<_> = input_data
+        .all_items()
+        .into_iter()
+        .filter_map(|item| item.tracking)
+        .collect();
+
+    let mut candidates = BTreeMap::new();
+    for label in labels.iter() {
+        for issue in query.query_tracking_issues(&RUSTC_REPO, label).await? {
+            if !known.contains(&issue.number) {
+                candidates.insert(issue.number, issue);
+            }
+        }
+    }
+    if candidates.is_empty() {
+        eprintln!("no untracked tracking issues found");
+        if section.is_some() {
+            eprintln!("data.yml is not changed");
+        }
+        return Ok(());
+    }
+    let mut candidates: Vec<_> = candidates.into_values().collect();
+    candidates.sort_by_key(|issue| issue.created_at);
+    for issue in candidates.iter() {
+        println!(
+            "#{} ({}) {}",
+            issue.number,
+            issue.created_at.date_naive(),
+            issue.title
+        );
+    }
+
+    let section = match section {
+        Some(section) => section,
+        None => return Ok(()),
+    };
+    let mut ids = input_data.ids();
+    let mut document = Document::new(&text);
+    let mut added = 0;
+    for issue in candidates.iter() {
+        let (id, mut lines) = add::item_lines(issue);
+        if !ids.insert(id.clone()) {
+            eprintln!("skipping #{}, item `{}` already exists", issue.number, id);
+            continue;
+        }
+        lines.push("draft: true".to_string());
+        document.append_item(section, &lines)?;
+        added += 1;
+    }
+    let new_text = document.text();
+    InputData::parse(&new_text).context("added items are invalid")?;
+    fs::write(DATA_FILE, new_text).context("failed to write data.yml")?;
+    eprintln!("added {} draft items to {}", added, section);
+    Ok(())
+}
@@ -1,52 +1,85 @@
 use crate::data::input::FetchList;
-use crate::data::{Issue, IssueId};
+use crate::data::{Issue, IssueId, LinkedPr};
 use crate::query::{GitHubQuery, Repo};
-use anyhow::{Error, Result};
+use anyhow::{Context, Error, Result};
+use chrono::{DateTime, Duration, Utc};
 use futures_util::future::ok;
 use futures_util::stream::{FuturesUnordered, TryStreamExt};
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
-use std::collections::HashMap;
-use std::fs::File;
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::env;
+
+/// Default of `CACHE_MAX_AGE`, in hours.
+const DEFAULT_MAX_AGE: i64 = 24;
 
 #[serde_as]
-#[derive(Default, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
 pub struct IssueData {
     #[serde_as(as = "Vec<(_, _)>")]
-    pub labels: HashMap<(Repo, String), Vec<IssueId>>,
+    pub labels: BTreeMap<(Repo, String), Vec<IssueId>>,
+    #[serde_as(as = "Vec<(_, _)>")]
+    pub issues: BTreeMap<(Repo, IssueId), Issue>,
+    /// When each label was fetched, missing for labels fetched before
+    /// this was recorded.
+    #[serde_as(as = "Vec<(_, _)>")]
+    pub labels_fetched: BTreeMap<(Repo, String), DateTime<Utc>>,
+    /// When each issue was fetched, missing for issues fetched before
+    /// this was recorded.
+    #[serde_as(as = "Vec<(_, _)>")]
+    pub issues_fetched: BTreeMap<(Repo, IssueId), DateTime<Utc>>,
+    /// Bodies of tracking issues, which are parsed for their task lists.
+    #[serde_as(as = "Vec<(_, _)>")]
+    pub bodies: BTreeMap<(Repo, IssueId), String>,
+    /// When each body was fetched, missing for bodies fetched before this
+    /// was recorded.
+    #[serde_as(as = "Vec<(_, _)>")]
+    pub bodies_fetched: BTreeMap<(Repo, IssueId), DateTime<Utc>>,
+    /// Pull requests cross-referencing tracking issues.
     #[serde_as(as = "Vec<(_, _)>")]
-    pub issues: HashMap<(Repo, IssueId), Issue>,
+    pub linked_prs: BTreeMap<(Repo, IssueId), Vec<LinkedPr>>,
+    /// When pull requests linked to each tracking issue were fetched,
+    /// missing for those fetched before this was recorded.
+    #[serde_as(as = "Vec<(_, _)>")]
+    pub linked_prs_fetched: BTreeMap<(Repo, IssueId), DateTime<Utc>>,
+    /// Latest release tag of rust-lang/rust when the data was last used,
+    /// which stabilization states of items depend on.
+    pub latest_tag: Option<String>,
 }
 
-impl IssueData {
-    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
-        let file = File::open(path)?;
-        Ok(serde_json::from_reader(file)?)
-    }
-
-    pub fn store_to_file(&self, path: impl AsRef<Path>) -> Result<()> {
-        let file = File::create(path)?;
-        serde_json::to_writer(file, self)?;
-        Ok(())
-    }
+/// Get how long fetched data is used before being fetched again, from the
+/// `CACHE_MAX_AGE` environment variable in hours.
+pub fn max_age_from_env() -> Result<Duration> {
+    let hours = match env::var("CACHE_MAX_AGE") {
+        Ok(hours) => hours.parse().context("invalid CACHE_MAX_AGE")?,
+        Err(env::VarError::NotPresent) => DEFAULT_MAX_AGE,
+        Err(e) => return Err(e).context("invalid CACHE_MAX_AGE"),
+    };
+    Ok(Duration::hours(hours))
+}
 
-    /// Fetch and fill into self when corresponding information does not exist.
-    /// Nothing would be updated if everything is available.
+impl IssueData {
+    /// Fetch and fill into self when corresponding information does not exist,
+    /// or was fetched longer than `max_age` ago or at an unknown time.
+    /// Nothing would be updated if everything is available and fresh.
     ///
     /// Returns whether anything is updated when succeeded.
     pub async fn fetch_data(
         &mut self,
         query: &GitHubQuery<'_>,
         fetch_list: &FetchList<'_>,
+        max_age: Duration,
     ) -> Result<bool> {
         let mut updated = false;
+        let now = Utc::now();
+        let is_fresh =
+            |time: Option<&DateTime<Utc>>| time.is_some_and(|time| now - *time < max_age);
         fetch_list
             .labels
             .iter()
             .filter_map(|(repo, label)| {
                 let key = (repo.clone(), label.to_string());
-                if self.labels.contains_key(&key) {
+                if self.labels.contains_key(&key) && is_fresh(self.labels_fetched.get(&key)) {
                     None
                 } else {
                     Some(async {
@@ -64,9 +97,11 @@ impl IssueData {
                     .map(|issue| {
                         let id = issue.number;
                         self.issues.insert((repo.clone(), id), issue);
+                        self.issues_fetched.insert((repo.clone(), id), now);
                         id
                     })
                     .collect();
+                self.labels_fetched.insert(key.clone(), now);
                 self.labels.insert(key, issues);
                 updated = true;
                 ok(())
@@ -78,7 +113,7 @@ impl IssueData {
             .iter()
             .filter_map(|(repo, issue_id)| {
                 let key = (repo.clone(), *issue_id);
-                if self.issues.contains_key(&key) {
+                if self.issues.contains_key(&key) && is_fresh(self.issues_fetched.get(&key)) {
                     None
                 } else {
                     Some(async {
@@ -90,12 +125,62 @@ impl IssueData {
             })
             .collect::<FuturesUnordered<_>>()
             .try_for_each_concurrent(None, |(key, issue)| {
+                self.issues_fetched.insert(key.clone(), now);
                 self.issues.insert(key, issue);
                 updated = true;
                 ok(())
             })
             .await?;
 
+        fetch_list
+            .tracking
+            .iter()
+            .filter_map(|(repo, issue_id)| {
+                let key = (repo.clone(), *issue_id);
+                if self.bodies.contains_key(&key) && is_fresh(self.bodies_fetched.get(&key)) {
+                    None
+                } else {
+                    Some(async {
+                        let (repo, issue_id) = &key;
+                        let issue = query.query_tracking_issue(repo, *issue_id).await?;
+                        Ok::<_, Error>((key, issue.body))
+                    })
+                }
+            })
+            .collect::<FuturesUnordered<_>>()
+            .try_for_each_concurrent(None, |(key, body)| {
+                self.bodies_fetched.insert(key.clone(), now);
+                self.bodies.insert(key, body);
+                updated = true;
+                ok(())
+            })
+            .await?;
+
+        fetch_list
+            .tracking
+            .iter()
+            .filter_map(|(repo, issue_id)| {
+                let key = (repo.clone(), *issue_id);
+                if self.linked_prs.contains_key(&key) && is_fresh(self.linked_prs_fetched.get(&key))
+                {
+                    None
+                } else {
+                    Some(async {
+                        let (repo, issue_id) = &key;
+                        let prs = query.query_linked_prs(repo, *issue_id).await?;
+                        Ok::<_, Error>((key, prs))
+                    })
+                }
+            })
+            .collect::<FuturesUnordered<_>>()
+            .try_for_each_concurrent(None, |(key, prs)| {
+                self.linked_prs_fetched.insert(key.clone(), now);
+                self.linked_prs.insert(key, prs);
+                updated = true;
+                ok(())
+            })
+            .await?;
+
         Ok(updated)
     }
 }
@@ -0,0 +1,235 @@
+use super::Snapshot;
+use crate::HISTORY_FILE;
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use clap::Subcommand;
+use std::io::{self, Write};
+
+#[derive(Subcommand)]
+pub enum HistoryCommand {
+    /// List all snapshots
+    List,
+    /// Print issue counts of a label over time
+    Label {
+        /// Label in rust-lang/rust like `A-async-await`
+        label: String,
+        /// Only include snapshots taken on or after this date
+        #[arg(long, value_name = "YYYY-MM-DD")]
+        since: Option<NaiveDate>,
+    },
+    /// Print state of an item over time
+    Item {
+        /// Id of the item
+        id: String,
+        /// Only include snapshots taken on or after this date
+        #[arg(long, value_name = "YYYY-MM-DD")]
+        since: Option<NaiveDate>,
+    },
+    /// Print a snapshot in JSON
+    Show {
+        /// Id of the snapshot
+        id: u32,
+    },
+}
+
+pub fn run(command: HistoryCommand) -> Result<()> {
+    let snapshots = super::load(HISTORY_FILE).context("failed to read history")?;
+    if snapshots.is_empty() {
+        bail!("no history recorded yet, it is recorded on builds");
+    }
+    let out = &mut io::stdout().lock();
+    match command {
+        HistoryCommand::List => list(out, &snapshots),
+        HistoryCommand::Label { label, since } => label_history(out, &snapshots, &label, since),
+        HistoryCommand::Item { id, since } => item_history(out, &snapshots, &id, since),
+        HistoryCommand::Show { id } => show(out, &snapshots, id),
+    }
+}
+
+fn list(out: &mut impl Write, snapshots: &[Snapshot]) -> Result<()> {
+    for snapshot in snapshots {
+        let stable = snapshot
+            .items
+            .values()
+            .filter(|item| item.stabilized.is_some())
+            .count();
+        writeln!(
+            out,
+            "{}\t{}\t{} labels\t{}/{} items stabilized",
+            snapshot.id,
+            format_time(snapshot),
+            snapshot.labels.len(),
+            stable,
+            snapshot.items.len()
+        )?;
+    }
+    Ok(())
+}
+
+fn label_history(
+    out: &mut impl Write,
+    snapshots: &[Snapshot],
+    label: &str,
+    since: Option<NaiveDate>,
+) -> Result<()> {
+    let mut found = false;
+    for snapshot in filter_since(snapshots, since) {
+        if let Some(counts) = snapshot.labels.get(label) {
+            found = true;
+            writeln!(
+                out,
+                "{}\t{}\t{} open\t{} closed",
+                snapshot.id,
+                format_time(snapshot),
+                counts.open,
+                counts.closed
+            )?;
+        }
+    }
+    if !found {
+        bail!("label `{}` is not recorded in history", label);
+    }
+    Ok(())
+}
+
+fn item_history(
+    out: &mut impl Write,
+    snapshots: &[Snapshot],
+    id: &str,
+    since: Option<NaiveDate>,
+) -> Result<()> {
+    let mut found = false;
+    for snapshot in filter_since(snapshots, since) {
+        if let Some(item) = snapshot.items.get(id) {
+            found = true;
+            let tracking = match item.tracking_open {
+                Some(true) => "tracking open",
+                Some(false) => "tracking closed",
+                None => "no tracking",
+            };
+            let stabilized = match &item.stabilized {
+                Some(s) => format!("{} {}", s.state.name(), s.version),
+                None => "not stabilized".to_string(),
+            };
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{}",
+                snapshot.id,
+                format_time(snapshot),
+                tracking,
+                stabilized
+            )?;
+        }
+    }
+    if !found {
+        bail!("item `{}` is not recorded in history", id);
+    }
+    Ok(())
+}
+
+fn show(out: &mut impl Write, snapshots: &[Snapshot], id: u32) -> Result<()> {
+    match snapshots.iter().find(|snapshot| snapshot.id == id) {
+        Some(snapshot) => {
+            writeln!(out, "{}", serde_json::to_string_pretty(snapshot)?)?;
+            Ok(())
+        }
+        None => bail!("snapshot {} doesn't exist", id),
+    }
+}
+
+fn filter_since(
+    snapshots: &[Snapshot],
+    since: Option<NaiveDate>,
+) -> impl Iterator<Item = &Snapshot> {
+    snapshots
+        .iter()
+        .filter(move |snapshot| since.is_none_or(|date| snapshot.time.date_naive() >= date))
+}
+
+fn format_time(snapshot: &Snapshot) -> String {
+    snapshot.time.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::{ItemState, LabelCounts};
+    use chrono::{TimeZone, Utc};
+    use std::collections::BTreeMap;
+
+    fn snapshot(id: u32, day: u32, open: Option<usize>, tracking_open: bool) -> Snapshot {
+        let counts = open.map(|open| ("A-a".to_string(), LabelCounts { open, closed: 1 }));
+        let state = ItemState {
+            tracking_open: Some(tracking_open),
+            stabilized: None,
+        };
+        Snapshot {
+            id,
+            time: Utc.with_ymd_and_hms(2020, 1, day, 0, 0, 0).unwrap(),
+            labels: counts.into_iter().collect(),
+            items: BTreeMap::from([("a".to_string(), state)]),
+        }
+    }
+
+    fn history() -> Vec<Snapshot> {
+        vec![
+            snapshot(1, 1, Some(3), true),
+            snapshot(2, 2, None, true),
+            snapshot(3, 3, Some(2), false),
+        ]
+    }
+
+    fn output(f: impl FnOnce(&mut Vec<u8>) -> Result<()>) -> Result<String> {
+        let mut out = Vec::new();
+        f(&mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn list_snapshots() {
+        let history = history();
+        let text = output(|out| list(out, &history)).unwrap();
+        assert_eq!(text.lines().count(), 3);
+        assert_eq!(
+            text.lines().next(),
+            Some("1\t2020-01-01 00:00:00 UTC\t1 labels\t0/1 items stabilized")
+        );
+    }
+
+    #[test]
+    fn label() {
+        let history = history();
+        let text = output(|out| label_history(out, &history, "A-a", None)).unwrap();
+        assert_eq!(
+            text,
+            "1\t2020-01-01 00:00:00 UTC\t3 open\t1 closed\n\
+             3\t2020-01-03 00:00:00 UTC\t2 open\t1 closed\n"
+        );
+        let since = NaiveDate::from_ymd_opt(2020, 1, 2);
+        let text = output(|out| label_history(out, &history, "A-a", since)).unwrap();
+        assert_eq!(text.lines().count(), 1);
+        assert!(output(|out| label_history(out, &history, "A-b", None)).is_err());
+    }
+
+    #[test]
+    fn item() {
+        let history = history();
+        let since = NaiveDate::from_ymd_opt(2020, 1, 2);
+        let text = output(|out| item_history(out, &history, "a", since)).unwrap();
+        assert_eq!(
+            text,
+            "2\t2020-01-02 00:00:00 UTC\ttracking open\tnot stabilized\n\
+             3\t2020-01-03 00:00:00 UTC\ttracking closed\tnot stabilized\n"
+        );
+        assert!(output(|out| item_history(out, &history, "b", None)).is_err());
+    }
+
+    #[test]
+    fn show_snapshot() {
+        let history = history();
+        let text = output(|out| show(out, &history, 2)).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(value["id"], 2);
+        assert!(output(|out| show(out, &history, 4)).is_err());
+    }
+}
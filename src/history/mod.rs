@@ -0,0 +1,267 @@
+//! Dated snapshots of the tracker state, appended on builds which see changes.
+//!
+//! Snapshots are stored one per line in a JSON Lines file, so that
+//! appending doesn't need to rewrite existing history. The whole history is
+//! also published with the site, where builds given a previous build read
+//! it from instead, so that it persists across builds in CI.
+
+use crate::changes::StabilizationState;
+use crate::data::output::Item;
+use crate::page_gen::api;
+use crate::previous::PreviousSource;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+pub mod command;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Snapshot {
+    /// Sequence number of the snapshot, starting from 1.
+    pub id: u32,
+    pub time: DateTime<Utc>,
+    /// Issue counts of labels in rust-lang/rust.
+    pub labels: BTreeMap<String, LabelCounts>,
+    pub items: BTreeMap<String, ItemState>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct LabelCounts {
+    pub open: usize,
+    pub closed: usize,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ItemState {
+    /// Whether the tracking issue is open, if there is one.
+    pub tracking_open: Option<bool>,
+    pub stabilized: Option<StabilizationState>,
+}
+
+impl Snapshot {
+    /// Take a snapshot of the given sections. Labels for which `is_fresh`
+    /// returns false are left out, since their counts haven't been fetched
+    /// again and would only repeat an earlier snapshot.
+    pub fn new(
+        id: u32,
+        time: DateTime<Utc>,
+        sections: &BTreeMap<String, Vec<Item>>,
+        is_fresh: impl Fn(&str) -> bool,
+    ) -> Self {
+        let mut snapshot = Snapshot {
+            id,
+            time,
+            labels: BTreeMap::new(),
+            items: BTreeMap::new(),
+        };
+        let mut items: Vec<_> = sections.values().flatten().collect();
+        while let Some(item) = items.pop() {
+            items.extend(item.deps.iter());
+            if let Some(label) = item.issue_label.as_ref().filter(|label| is_fresh(label)) {
+                let open = item.issues.iter().filter(|issue| issue.open).count();
+                let counts = LabelCounts {
+                    open,
+                    closed: item.issues.len() - open,
+                };
+                snapshot.labels.insert(label.clone(), counts);
+            }
+            let state = ItemState {
+                tracking_open: item.tracking.as_ref().map(|issue| issue.open),
+                stabilized: item.stabilized.as_ref().map(|s| StabilizationState {
+                    version: s.version.clone(),
+                    state: s.state,
+                }),
+            };
+            snapshot.items.insert(item.id.clone(), state);
+        }
+        snapshot
+    }
+
+    /// Check whether this snapshot adds nothing to the given history, i.e.
+    /// it's taken at the same time as the last one, or all its items and
+    /// label counts are the same as the last recorded ones.
+    pub fn repeats(&self, history: &[Snapshot]) -> bool {
+        let last = match history.last() {
+            Some(last) => last,
+            None => return false,
+        };
+        if self.time == last.time {
+            return true;
+        }
+        // Labels may be missing from some snapshots, so compare each label
+        // against the last snapshot which has it.
+        self.items == last.items
+            && self.labels.iter().all(|(label, counts)| {
+                let last_counts = history
+                    .iter()
+                    .rev()
+                    .find_map(|snapshot| snapshot.labels.get(label));
+                last_counts == Some(counts)
+            })
+    }
+}
+
+/// Read all snapshots from the given file, oldest first.
+///
+/// Returns an empty list if the file doesn't exist.
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<Snapshot>> {
+    match fs::read_to_string(path) {
+        Ok(text) => parse(&text),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Read all snapshots published by the given previous build, oldest first.
+pub async fn load_previous(source: &PreviousSource, client: &Client) -> Result<Vec<Snapshot>> {
+    match source.read(client, api::HISTORY_FILE).await? {
+        Some(text) => parse(&text),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn parse(text: &str) -> Result<Vec<Snapshot>> {
+    let mut snapshots = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let snapshot = serde_json::from_str(line)
+            .with_context(|| format!("failed to parse snapshot on line {}", i + 1))?;
+        snapshots.push(snapshot);
+    }
+    Ok(snapshots)
+}
+
+/// Serialize the given snapshot into a line of the history file.
+pub fn to_line(snapshot: &Snapshot) -> Result<String> {
+    let mut line = serde_json::to_string(snapshot)?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// Append the given snapshot to the history file.
+pub fn append(path: impl AsRef<Path>, snapshot: &Snapshot) -> Result<()> {
+    let line = to_line(snapshot)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    // Write the whole line at once so that an interrupted write doesn't
+    // leave a partial line in the middle of the file.
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::output::{Stabilization, VersionState};
+    use crate::data::Issue;
+    use chrono::TimeZone;
+
+    fn time(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2020, 1, day, 0, 0, 0).unwrap()
+    }
+
+    fn issue(number: u32, open: bool) -> Issue {
+        Issue {
+            number,
+            title: format!("Issue {}", number),
+            open,
+            pull_request: None,
+        }
+    }
+
+    fn sections() -> BTreeMap<String, Vec<Item>> {
+        let mut a = Item::new_for_test("a", "A");
+        a.tracking = Some(issue(1, true));
+        a.issue_label = Some("A-a".to_string());
+        a.issues = vec![issue(2, true), issue(3, false), issue(4, true)];
+        let mut b = Item::new_for_test("b", "B");
+        b.issue_label = Some("A-b".to_string());
+        b.stabilized = Some(Stabilization {
+            state: VersionState::Beta,
+            version: "1.40".to_string(),
+            release_date: None,
+            pr: issue(5, false),
+        });
+        a.deps.push(b);
+        BTreeMap::from([("Section".to_string(), vec![a])])
+    }
+
+    #[test]
+    fn new_snapshot() {
+        let snapshot = Snapshot::new(1, time(1), &sections(), |label| label == "A-a");
+        let counts = LabelCounts { open: 2, closed: 1 };
+        assert_eq!(
+            snapshot.labels,
+            BTreeMap::from([("A-a".to_string(), counts)])
+        );
+        let a = ItemState {
+            tracking_open: Some(true),
+            stabilized: None,
+        };
+        let b = ItemState {
+            tracking_open: None,
+            stabilized: Some(StabilizationState {
+                version: "1.40".to_string(),
+                state: VersionState::Beta,
+            }),
+        };
+        let items = BTreeMap::from([("a".to_string(), a), ("b".to_string(), b)]);
+        assert_eq!(snapshot.items, items);
+    }
+
+    #[test]
+    fn repeats() {
+        let first = Snapshot::new(1, time(1), &sections(), |_| true);
+        assert!(!first.repeats(&[]));
+        let history = vec![first];
+        // Same state, or same time as the last snapshot.
+        assert!(Snapshot::new(2, time(2), &sections(), |_| true).repeats(&history));
+        let mut sections = sections();
+        sections.get_mut("Section").unwrap()[0].issues.pop();
+        assert!(Snapshot::new(2, time(1), &sections, |_| true).repeats(&history));
+        // A label count changed.
+        assert!(!Snapshot::new(2, time(2), &sections, |_| true).repeats(&history));
+        // A label left out is compared against the last snapshot having it.
+        let history = vec![
+            history.into_iter().next().unwrap(),
+            Snapshot::new(2, time(2), &self::sections(), |label| label == "A-b"),
+        ];
+        assert!(!Snapshot::new(3, time(3), &sections, |_| true).repeats(&history));
+        assert!(Snapshot::new(3, time(3), &self::sections(), |_| true).repeats(&history));
+        // An item state changed.
+        let mut sections = self::sections();
+        sections.get_mut("Section").unwrap()[0].tracking = Some(issue(1, false));
+        assert!(!Snapshot::new(3, time(3), &sections, |_| false).repeats(&history));
+    }
+
+    #[test]
+    fn append_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        assert!(load(&path).unwrap().is_empty());
+        let first = Snapshot::new(1, time(1), &sections(), |_| true);
+        let second = Snapshot::new(2, time(2), &sections(), |_| false);
+        append(&path, &first).unwrap();
+        append(&path, &second).unwrap();
+        let history = load(&path).unwrap();
+        let ids: Vec<_> = history.iter().map(|snapshot| snapshot.id).collect();
+        assert_eq!(ids, [1, 2]);
+        assert_eq!(history[0].labels, first.labels);
+        assert_eq!(history[1].items, second.items);
+        assert_eq!(history[1].time, time(2));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let line = to_line(&Snapshot::new(1, time(1), &sections(), |_| true)).unwrap();
+        assert_eq!(parse(&format!("{}\n{}", line, line)).unwrap().len(), 2);
+        let error = parse(&format!("{}{{", line)).unwrap_err();
+        assert!(error.to_string().contains("line 2"), "{}", error);
+    }
+}
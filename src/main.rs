@@ -1,28 +1,53 @@
+use crate::cache::Cache;
+use crate::changes::Previous;
+use crate::cli::{Args, BuildArgs, Command};
 use crate::data::input::InputData;
-use crate::data::output::OutputData;
+use crate::data::output::{self, OutputData};
+use crate::data::IssueId;
 use crate::fetcher::IssueData;
+use crate::history::Snapshot;
 use crate::page_gen::PageGenData;
+use crate::previous::PreviousSource;
 use crate::query::{GitHubQuery, Repo};
-use anyhow::{Context, Result};
+use crate::schema::SchemaKind;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use clap::Parser;
 use futures_util::future::try_join;
 use once_cell::sync::Lazy;
 use semver::Version;
 use std::env;
 use std::fs;
-use std::io;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+mod add;
+mod assets;
+mod cache;
+mod changes;
+mod cli;
 mod data;
+mod diff;
+mod discover;
 mod fetcher;
+mod history;
+mod out_dir;
 mod page_gen;
 mod posts;
+mod previous;
 mod query;
+mod report;
+mod schema;
+mod suggest;
 
 const DATA_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/data.yml");
 const POSTS_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/posts.yml");
 const CACHE_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/cache.json");
+const CACHE_DB: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/cache.sqlite");
+const HISTORY_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/history.jsonl");
 
 static OUT_DIR: Lazy<&'static Path> = Lazy::new(|| Path::new("_site"));
+static STAGING_DIR: Lazy<&'static Path> = Lazy::new(|| Path::new("_site.staging"));
+static PREV_DIR: Lazy<&'static Path> = Lazy::new(|| Path::new("_site.prev"));
 static RFC_REPO: Lazy<Repo> = Lazy::new(|| Repo::new("rust-lang", "rfcs"));
 static RUSTC_REPO: Lazy<Repo> = Lazy::new(|| Repo::new("rust-lang", "rust"));
 
@@ -30,82 +55,189 @@ static RUSTC_REPO: Lazy<Repo> = Lazy::new(|| Repo::new("rust-lang", "rust"));
 async fn main() -> Result<()> {
     let _ = dotenv::dotenv();
     env_logger::init();
+    let args = Args::parse();
+    match args.command.unwrap_or(Command::Build(args.build)) {
+        Command::Add { tracking, section } => add_item(tracking, &section).await,
+        Command::Build(args) => build(args).await,
+        Command::Cache { command } => cache::command::run(command),
+        Command::Check => check(),
+        Command::Diff { old, new, format } => diff::run(&old, &new, format),
+        // `--section` is only accepted with `--write`.
+        Command::Discover {
+            labels, section, ..
+        } => discover_items(&labels, section.as_deref()).await,
+        Command::History { command } => history::command::run(command),
+        Command::Report { format, output } => print_report(format, output).await,
+        Command::Schema { kind, write } => print_schema(kind, write),
+        Command::Suggest => print_suggestions().await,
+    }
+}
+
+async fn build(args: BuildArgs) -> Result<()> {
     let token = env::var("GITHUB_TOKEN")?;
     let client = reqwest::Client::new();
     let query = GitHubQuery::new(&client, &token);
-    let data = load_page_gen_data(&query).await?;
+    let time = match args.source_date_epoch {
+        Some(timestamp) => Utc
+            .timestamp_opt(timestamp, 0)
+            .single()
+            .context("invalid SOURCE_DATE_EPOCH")?,
+        None => Utc::now(),
+    };
+    let previous = args.previous.as_ref();
+    let data = load_page_gen_data(&query, &client, time, previous, args.rfcs_dir.as_deref())
+        .await?;
 
-    // Generate page
-    if OUT_DIR.is_dir() {
-        clear_dir(&OUT_DIR).context("failed to clear out dir")?;
-    } else {
-        fs::create_dir_all(*OUT_DIR).context("failed to create out dir")?;
+    // Generate page into the staging dir, so that the out dir is only
+    // replaced when everything succeeds.
+    if STAGING_DIR.exists() {
+        fs::remove_dir_all(*STAGING_DIR).context("failed to remove staging dir")?;
+    }
+    fs::create_dir_all(*STAGING_DIR).context("failed to create staging dir")?;
+    page_gen::generate(&data, &STAGING_DIR, args.templates.as_deref())
+        .context("failed to generate data")?;
+    assets::copy_static_files(&STAGING_DIR, args.static_dir.as_deref())
+        .context("failed to copy static files")?;
+    fs::write(STAGING_DIR.join("CNAME"), assets::CNAME).context("failed to write CNAME")?;
+    out_dir::swap(&STAGING_DIR, &OUT_DIR, &PREV_DIR)
+        .context("failed to move staging dir into place")?;
+    // History read from a given previous build lives in the published site
+    // rather than the local history file.
+    if let (Some(snapshot), None) = (&data.snapshot, previous) {
+        history::append(HISTORY_FILE, snapshot).context("failed to record history")?;
     }
-    page_gen::generate(&data).context("failed to generate data")?;
-    copy_static_files().context("failed to copy static files")?;
-    fs::copy(
-        concat!(env!("CARGO_MANIFEST_DIR"), "/CNAME"),
-        OUT_DIR.join("CNAME"),
-    ).context("failed to copy CNAME")?;
     Ok(())
 }
 
-async fn load_page_gen_data(query: &GitHubQuery<'_>) -> Result<PageGenData> {
-    let input_data = InputData::from_file(DATA_FILE).context("failed to read input data")?;
-    let fetch_list = input_data.get_fetch_list();
-
-    let mut issue_data = IssueData::from_file(CACHE_FILE).unwrap_or_default();
-    let (latest_tag, _) = try_join(
-        query.query_latest_tag(&RUSTC_REPO),
-        issue_data.fetch_data(query, &fetch_list),
-    )
-    .await?;
-    issue_data.store_to_file(CACHE_FILE).context("failed to store to cache file")?;
-
-    let latest_stable = Version::parse(&latest_tag)?;
-    let output_data = OutputData::from_input(input_data, &issue_data, &latest_stable);
+async fn add_item(tracking: IssueId, section: &str) -> Result<()> {
+    let token = env::var("GITHUB_TOKEN")?;
+    let client = reqwest::Client::new();
+    let query = GitHubQuery::new(&client, &token);
+    add::run(&query, tracking, section).await
+}
 
-    Ok(PageGenData {
-        items: output_data.0,
-        posts: posts::load_posts().context("failed to load posts")?,
-    })
+async fn discover_items(labels: &[String], section: Option<&str>) -> Result<()> {
+    let token = env::var("GITHUB_TOKEN")?;
+    let client = reqwest::Client::new();
+    let query = GitHubQuery::new(&client, &token);
+    discover::run(&query, labels, section).await
 }
 
-fn clear_dir(dir: &Path) -> io::Result<()> {
-    for entry in dir.read_dir()? {
-        let entry = entry?;
-        let file_type = entry.file_type()?;
-        if file_type.is_file() {
-            fs::remove_file(&entry.path())?;
-        } else if file_type.is_dir() {
-            fs::remove_dir_all(&entry.path())?;
-        } else {
-            unreachable!("unknown file type");
+fn check() -> Result<()> {
+    for (kind, path) in [(SchemaKind::Data, DATA_FILE), (SchemaKind::Posts, POSTS_FILE)] {
+        schema::check_schema_file(kind)?;
+        schema::validate_file(kind, path)?;
+    }
+    let input_data = InputData::from_file(DATA_FILE).context("failed to read input data")?;
+    for item in input_data.all_items() {
+        let version = item.stabilized.as_ref().and_then(|s| s.version.as_ref());
+        if let Some(version) = version.filter(|version| output::release_date(version).is_none()) {
+            bail!(
+                "item `{}` is stabilized in `{}`, which is not a valid 1.x version",
+                item.id(),
+                version
+            );
+        }
+    }
+    let ids = input_data.ids();
+    for post in posts::load_posts().context("failed to load posts")? {
+        if let Some(id) = post.items.iter().find(|id| !ids.contains(*id)) {
+            bail!("post {} refers to unknown item `{}`", post.url, id);
         }
     }
     Ok(())
 }
 
-fn copy_static_files() -> io::Result<()> {
-    let src = concat!(env!("CARGO_MANIFEST_DIR"), "/static");
-    copy_dir(src.as_ref(), OUT_DIR.as_ref())
+async fn print_report(format: report::Format, output: Option<PathBuf>) -> Result<()> {
+    let token = env::var("GITHUB_TOKEN")?;
+    let client = reqwest::Client::new();
+    let query = GitHubQuery::new(&client, &token);
+    let (output_data, _) = load_output_data(&query, None).await?;
+    let report = report::render(format, &output_data.0);
+    match output {
+        Some(path) => fs::write(&path, report)
+            .with_context(|| format!("failed to write {}", path.display()))?,
+        None => print!("{}", report),
+    }
+    Ok(())
 }
 
-fn copy_dir(src: &Path, dest: &Path) -> io::Result<()> {
-    for entry in src.read_dir()? {
-        let entry = entry?;
-        let path = entry.path();
-        let file_name = entry.file_name();
-        let file_type = entry.file_type()?;
-        if file_type.is_file() {
-            fs::copy(path, dest.join(file_name))?;
-        } else if file_type.is_dir() {
-            let dest_dir = dest.join(file_name);
-            fs::create_dir(&dest_dir)?;
-            copy_dir(&path, &dest_dir)?;
-        } else {
-            unreachable!("unknown file type");
-        }
+async fn print_suggestions() -> Result<()> {
+    let token = env::var("GITHUB_TOKEN")?;
+    let client = reqwest::Client::new();
+    let query = GitHubQuery::new(&client, &token);
+    suggest::run(&query).await
+}
+
+fn print_schema(kind: SchemaKind, write: bool) -> Result<()> {
+    if !write {
+        print!("{}", kind.to_json()?);
+        return Ok(());
+    }
+    for kind in [SchemaKind::Data, SchemaKind::Posts] {
+        let path = kind.schema_file();
+        fs::write(path, kind.to_json()?).with_context(|| format!("failed to write {}", path))?;
     }
     Ok(())
 }
+
+async fn load_page_gen_data(
+    query: &GitHubQuery<'_>,
+    client: &reqwest::Client,
+    time: DateTime<Utc>,
+    previous: Option<&PreviousSource>,
+    rfcs_dir: Option<&Path>,
+) -> Result<PageGenData> {
+    let (output_data, issue_data) = load_output_data(query, rfcs_dir).await?;
+    let out_dir = PreviousSource::Dir(OUT_DIR.to_path_buf());
+    let source = previous.unwrap_or(&out_dir);
+    let changes = Previous::load(source, client)
+        .await
+        .with_context(|| format!("failed to load previous build from {}", source))?
+        .update(&output_data.0, time);
+    let history = match previous {
+        Some(source) => history::load_previous(source, client)
+            .await
+            .with_context(|| format!("failed to read history from {}", source))?,
+        None => history::load(HISTORY_FILE).context("failed to read history")?,
+    };
+    let last = history.last().map(|snapshot| (snapshot.id, snapshot.time));
+    let id = last.map_or(0, |(id, _)| id) + 1;
+    let snapshot = Snapshot::new(id, time, &output_data.0, |label| {
+        let key = (RUSTC_REPO.clone(), label.to_string());
+        let fetched = issue_data.labels_fetched.get(&key);
+        // Only count labels fetched again since the last snapshot.
+        last.is_none_or(|(_, last_time)| fetched.is_some_and(|fetched| *fetched > last_time))
+    });
+    let snapshot = Some(snapshot).filter(|snapshot| !snapshot.repeats(&history));
+    Ok(PageGenData {
+        items: output_data.0,
+        posts: posts::load_posts().context("failed to load posts")?,
+        changes,
+        history,
+        snapshot,
+        time,
+    })
+}
+
+async fn load_output_data(
+    query: &GitHubQuery<'_>,
+    rfcs_dir: Option<&Path>,
+) -> Result<(OutputData, IssueData)> {
+    let input_data = InputData::from_file(DATA_FILE).context("failed to read input data")?;
+    let fetch_list = input_data.get_fetch_list();
+
+    let mut cache = Cache::open()?;
+    let mut issue_data = cache.load_or_default()?;
+    let (latest_tag, _) = try_join(
+        query.query_latest_tag(&RUSTC_REPO),
+        issue_data.fetch_data(query, &fetch_list, fetcher::max_age_from_env()?),
+    )
+    .await?;
+    issue_data.latest_tag = Some(latest_tag.clone());
+    cache.store(&issue_data)?;
+
+    let latest_stable = Version::parse(&latest_tag)?;
+    let output_data = OutputData::from_input(input_data, &issue_data, &latest_stable, rfcs_dir);
+    Ok((output_data, issue_data))
+}
@@ -0,0 +1,224 @@
+//! Switching the output directory to a new build.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+#[cfg(unix)]
+use std::path::PathBuf;
+#[cfg(unix)]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Move the staging dir into place as the out dir, and keep the previous
+/// out dir as the prev dir for rollback.
+///
+/// Both are symlinks to build dirs next to them, so that the out dir is
+/// switched with a single rename, and is never missing or incomplete.
+#[cfg(unix)]
+pub fn swap(staging_dir: &Path, out_dir: &Path, prev_dir: &Path) -> io::Result<()> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    let build_dir = PathBuf::from(format!("{}.{}", out_dir.display(), nanos));
+    fs::rename(staging_dir, &build_dir)?;
+
+    // The out dir was a directory before it became a symlink, which has
+    // to be moved aside first, and moved back if switching fails.
+    let mut moved = None;
+    let old = match read_symlink(out_dir)? {
+        Some(target) => Some(target),
+        None if out_dir.exists() => {
+            let old = PathBuf::from(format!("{}.{}.old", out_dir.display(), nanos));
+            fs::rename(out_dir, &old)?;
+            moved = Some(old.clone());
+            Some(old)
+        }
+        None => None,
+    };
+    if let Err(e) = replace_symlink(&build_dir, out_dir) {
+        if let Some(old) = moved {
+            fs::rename(old, out_dir)?;
+        }
+        return Err(e);
+    }
+
+    let old = match old {
+        Some(old) => old,
+        None => return Ok(()),
+    };
+    let prev = read_symlink(prev_dir)?;
+    if prev.is_none() && prev_dir.exists() {
+        fs::remove_dir_all(prev_dir)?;
+    }
+    replace_symlink(&old, prev_dir)?;
+    if let Some(prev) = prev.filter(|prev| *prev != old && *prev != build_dir) {
+        fs::remove_dir_all(prev)?;
+    }
+    Ok(())
+}
+
+/// Move the staging dir into place as the out dir, and keep the previous
+/// out dir as the prev dir for rollback.
+///
+/// Without symlinks, the out dir is renamed aside before the staging dir
+/// takes its place, so it's briefly missing, but never incomplete.
+#[cfg(not(unix))]
+pub fn swap(staging_dir: &Path, out_dir: &Path, prev_dir: &Path) -> io::Result<()> {
+    if !staging_dir.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "staging dir doesn't exist",
+        ));
+    }
+    if !out_dir.exists() {
+        return fs::rename(staging_dir, out_dir);
+    }
+    if prev_dir.exists() {
+        fs::remove_dir_all(prev_dir)?;
+    }
+    fs::rename(out_dir, prev_dir)?;
+    if let Err(e) = fs::rename(staging_dir, out_dir) {
+        fs::rename(prev_dir, out_dir)?;
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Get the target of the given symlink, relative to the current directory,
+/// or `None` if it's not a symlink.
+#[cfg(unix)]
+fn read_symlink(link: &Path) -> io::Result<Option<PathBuf>> {
+    match fs::symlink_metadata(link) {
+        Ok(metadata) if metadata.file_type().is_symlink() => {
+            let target = fs::read_link(link)?;
+            Ok(Some(link.with_file_name(target)))
+        }
+        Ok(_) => Ok(None),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Point the given link at the target next to it, by renaming a new symlink
+/// over it, which replaces the link atomically.
+#[cfg(unix)]
+fn replace_symlink(target: &Path, link: &Path) -> io::Result<()> {
+    use std::os::unix::fs::symlink;
+
+    let tmp = PathBuf::from(format!("{}.tmp", link.display()));
+    match fs::remove_file(&tmp) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    // The target is relative to the directory of the link.
+    let name = target.file_name().expect("target has a file name");
+    symlink(name, &tmp)?;
+    fs::rename(&tmp, link)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    struct Dirs {
+        root: TempDir,
+        staging: PathBuf,
+        out: PathBuf,
+        prev: PathBuf,
+    }
+
+    impl Dirs {
+        fn new() -> Self {
+            let root = tempfile::tempdir().unwrap();
+            Dirs {
+                staging: root.path().join("_site.staging"),
+                out: root.path().join("_site"),
+                prev: root.path().join("_site.prev"),
+                root,
+            }
+        }
+
+        /// Generate a build with the given content into the staging dir.
+        fn stage(&self, content: &str) {
+            fs::create_dir_all(&self.staging).unwrap();
+            fs::write(self.staging.join("index.html"), content).unwrap();
+        }
+
+        fn swap(&self) -> io::Result<()> {
+            swap(&self.staging, &self.out, &self.prev)
+        }
+
+        fn read(dir: &Path) -> String {
+            fs::read_to_string(dir.join("index.html")).unwrap()
+        }
+
+        /// Count entries in the root, i.e. links and build dirs.
+        fn count(&self) -> usize {
+            fs::read_dir(self.root.path()).unwrap().count()
+        }
+    }
+
+    #[test]
+    fn first_run() {
+        let dirs = Dirs::new();
+        dirs.stage("1");
+        dirs.swap().unwrap();
+        assert_eq!(Dirs::read(&dirs.out), "1");
+        assert!(!dirs.staging.exists());
+        assert!(!dirs.prev.exists());
+    }
+
+    #[test]
+    fn out_dir_is_plain_dir() {
+        let dirs = Dirs::new();
+        fs::create_dir(&dirs.out).unwrap();
+        fs::write(dirs.out.join("index.html"), "0").unwrap();
+        dirs.stage("1");
+        dirs.swap().unwrap();
+        assert_eq!(Dirs::read(&dirs.out), "1");
+        assert_eq!(Dirs::read(&dirs.prev), "0");
+    }
+
+    #[test]
+    fn rotates_prev_dir() {
+        let dirs = Dirs::new();
+        for content in ["1", "2", "3"] {
+            dirs.stage(content);
+            dirs.swap().unwrap();
+        }
+        assert_eq!(Dirs::read(&dirs.out), "3");
+        assert_eq!(Dirs::read(&dirs.prev), "2");
+        // The build dir of the first run is removed.
+        let expected = if cfg!(unix) { 4 } else { 2 };
+        assert_eq!(dirs.count(), expected);
+    }
+
+    #[test]
+    fn failed_generation_leaves_out_dir() {
+        let dirs = Dirs::new();
+        dirs.stage("1");
+        dirs.swap().unwrap();
+        dirs.stage("2");
+        dirs.swap().unwrap();
+        let count = dirs.count();
+        // Generation failing leaves no staging dir to swap in.
+        assert!(dirs.swap().is_err());
+        assert_eq!(Dirs::read(&dirs.out), "2");
+        assert_eq!(Dirs::read(&dirs.prev), "1");
+        assert_eq!(dirs.count(), count);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_are_relative() {
+        let dirs = Dirs::new();
+        dirs.stage("1");
+        dirs.swap().unwrap();
+        let target = fs::read_link(&dirs.out).unwrap();
+        assert_eq!(target.components().count(), 1);
+        let target = read_symlink(&dirs.out).unwrap().unwrap();
+        assert_eq!(target.parent(), Some(dirs.root.path()));
+        assert_eq!(read_symlink(&dirs.staging).unwrap(), None);
+    }
+}
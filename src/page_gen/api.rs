@@ -0,0 +1,204 @@
+//! Machine-readable output of the tracker state.
+//!
+//! Types in this module form the public schema of `api/v1`, so fields should
+//! only be added, never renamed or removed, without bumping the version.
+
+use super::{ItemNode, PageGenData};
+use crate::changes::Change;
+use crate::data::output::{self, VersionState};
+use crate::data::questions::Question;
+use crate::data::tasks::Progress;
+use crate::data::{Issue, IssueId, Link, LinkedPr};
+use crate::history;
+use crate::posts::Post;
+use crate::query::Repo;
+use crate::RUSTC_REPO;
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+pub const VERSION: u32 = 1;
+const API_DIR: &str = "api/v1";
+pub const ITEMS_FILE: &str = "api/v1/items.json";
+pub const CHANGES_FILE: &str = "api/v1/changes.json";
+pub const HISTORY_FILE: &str = "api/v1/history.jsonl";
+
+#[derive(Serialize)]
+struct ItemsFile<'a> {
+    version: u32,
+    items: Vec<Item<'a>>,
+}
+
+#[derive(Serialize)]
+struct ItemFile<'a> {
+    version: u32,
+    item: &'a Item<'a>,
+}
+
+#[derive(Serialize)]
+struct PostsFile<'a> {
+    version: u32,
+    posts: &'a [Post],
+}
+
+#[derive(Serialize)]
+struct ChangesFile<'a> {
+    version: u32,
+    changes: &'a [Change],
+}
+
+#[derive(Serialize)]
+struct Item<'a> {
+    id: &'a str,
+    section: &'a str,
+    parent: Option<&'a str>,
+    title: &'a str,
+    rfc: Option<Rfc<'a>>,
+    tracking: Option<IssueRef<'a>>,
+    progress: Option<&'a Progress>,
+    questions: &'a [Question],
+    linked_prs: &'a [LinkedPr],
+    issue_label: Option<&'a str>,
+    issues: Vec<IssueRef<'a>>,
+    stabilized: Option<Stabilization<'a>>,
+    unresolved: Option<Rfc<'a>>,
+    link: Option<&'a Link>,
+    deps: Vec<&'a str>,
+}
+
+#[derive(Serialize)]
+struct IssueRef<'a> {
+    number: IssueId,
+    title: &'a str,
+    state: IssueState,
+    url: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+enum IssueState {
+    Open,
+    Closed,
+}
+
+#[derive(Serialize)]
+struct Rfc<'a> {
+    number: IssueId,
+    title: &'a str,
+    state: IssueState,
+    url: &'a str,
+    merged: bool,
+}
+
+#[derive(Serialize)]
+struct Stabilization<'a> {
+    version: &'a str,
+    state: &'a VersionState,
+    release_date: Option<NaiveDate>,
+    pr: IssueRef<'a>,
+}
+
+pub fn generate(data: &PageGenData, out_dir: &Path) -> Result<()> {
+    let items: Vec<_> = data.all_items().into_iter().map(convert_item).collect();
+
+    let api_dir = out_dir.join(API_DIR);
+    let item_dir = api_dir.join("items");
+    fs::create_dir_all(&item_dir)?;
+    for item in items.iter() {
+        let file = ItemFile {
+            version: VERSION,
+            item,
+        };
+        write_json(&item_dir.join(format!("{}.json", item.id)), &file)?;
+    }
+    let file = ItemsFile {
+        version: VERSION,
+        items,
+    };
+    write_json(&out_dir.join(ITEMS_FILE), &file)?;
+    let file = PostsFile {
+        version: VERSION,
+        posts: &data.posts,
+    };
+    write_json(&api_dir.join("posts.json"), &file)?;
+    let file = ChangesFile {
+        version: VERSION,
+        changes: &data.changes,
+    };
+    write_json(&out_dir.join(CHANGES_FILE), &file)?;
+    let mut history = String::new();
+    for snapshot in data.history.iter().chain(data.snapshot.as_ref()) {
+        history.push_str(&history::to_line(snapshot)?);
+    }
+    fs::write(out_dir.join(HISTORY_FILE), history)?;
+    Ok(())
+}
+
+fn convert_item(node: ItemNode<'_>) -> Item<'_> {
+    let item = node.item;
+    Item {
+        id: &item.id,
+        section: node.section,
+        parent: node.parent.map(|parent| parent.id.as_str()),
+        title: &item.title,
+        rfc: item.rfc.as_ref().map(convert_rfc),
+        tracking: item
+            .tracking
+            .as_ref()
+            .map(|i| convert_issue(&RUSTC_REPO, i)),
+        progress: item.progress.as_ref(),
+        questions: &item.questions,
+        linked_prs: &item.linked_prs,
+        issue_label: item.issue_label.as_deref(),
+        issues: item
+            .issues
+            .iter()
+            .map(|i| convert_issue(&RUSTC_REPO, i))
+            .collect(),
+        stabilized: item.stabilized.as_ref().map(|s| Stabilization {
+            version: &s.version,
+            state: &s.state,
+            release_date: s.release_date,
+            pr: convert_issue(&RUSTC_REPO, &s.pr),
+        }),
+        unresolved: item.unresolved.as_ref().map(convert_rfc),
+        link: item.link.as_ref(),
+        deps: item.deps.iter().map(|dep| dep.id.as_str()).collect(),
+    }
+}
+
+fn convert_issue<'a>(repo: &Repo, issue: &'a Issue) -> IssueRef<'a> {
+    IssueRef {
+        number: issue.number,
+        title: &issue.title,
+        state: convert_state(issue),
+        url: format!("https://github.com/{}/issues/{}", repo, issue.number),
+    }
+}
+
+fn convert_rfc(rfc: &output::Rfc) -> Rfc<'_> {
+    Rfc {
+        number: rfc.issue.number,
+        title: &rfc.issue.title,
+        state: convert_state(&rfc.issue),
+        url: &rfc.url,
+        merged: rfc.merged,
+    }
+}
+
+fn convert_state(issue: &Issue) -> IssueState {
+    if issue.open {
+        IssueState::Open
+    } else {
+        IssueState::Closed
+    }
+}
+
+fn write_json(path: &Path, value: &impl Serialize) -> Result<()> {
+    let mut json = serde_json::to_string_pretty(value)?;
+    json.push('\n');
+    fs::write(path, json)?;
+    Ok(())
+}
@@ -0,0 +1,277 @@
+//! Shields-style SVG status badges.
+
+use super::PageGenData;
+use crate::data::output::{Item, VersionState};
+use anyhow::Result;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+const BADGE_DIR: &str = "badges";
+
+const GREEN: &str = "#4c1";
+const YELLOW: &str = "#dfb317";
+const PURPLE: &str = "#9f5fbf";
+const RED: &str = "#e05d44";
+const BLUE: &str = "#007ec6";
+const GREY: &str = "#9f9f9f";
+
+struct Badge {
+    label: String,
+    message: String,
+    color: &'static str,
+}
+
+pub fn generate(data: &PageGenData, out_dir: &Path) -> Result<()> {
+    let dir = out_dir.join(BADGE_DIR);
+    fs::create_dir_all(&dir)?;
+    for node in data.all_items() {
+        let badge = item_badge(node.item);
+        fs::write(dir.join(format!("{}.svg", node.item.id)), badge.render())?;
+    }
+    for (section, items) in data.items.iter() {
+        let badge = section_badge(section, items);
+        fs::write(dir.join(format!("{}.svg", section)), badge.render())?;
+    }
+    Ok(())
+}
+
+fn item_badge(item: &Item) -> Badge {
+    let (message, color) = if item.unresolved.is_some() {
+        ("unresolved".to_string(), RED)
+    } else if item.link.is_some() {
+        ("workaround".to_string(), GREY)
+    } else {
+        match &item.stabilized {
+            Some(stabilized) => match stabilized.state {
+                VersionState::Stable => (format!("stable {}", stabilized.version), GREEN),
+                VersionState::Beta => (format!("beta {}", stabilized.version), YELLOW),
+                VersionState::Nightly => ("nightly".to_string(), PURPLE),
+            },
+            None => ("not stabilized".to_string(), GREY),
+        }
+    };
+    Badge {
+        label: "rust".to_string(),
+        message,
+        color,
+    }
+}
+
+fn section_badge(section: &str, items: &[Item]) -> Badge {
+    let stable = items
+        .iter()
+        .filter(|item| match &item.stabilized {
+            Some(stabilized) => stabilized.state == VersionState::Stable,
+            None => false,
+        })
+        .count();
+    let color = if stable == items.len() { GREEN } else { BLUE };
+    Badge {
+        label: section.replace('_', " "),
+        message: format!("{}/{} stabilized", stable, items.len()),
+        color,
+    }
+}
+
+impl Badge {
+    fn render(&self) -> String {
+        // Horizontal padding on each side of the text.
+        const PADDING: f64 = 5.0;
+        let label_width = (text_width(&self.label) + 2.0 * PADDING).round();
+        let message_width = (text_width(&self.message) + 2.0 * PADDING).round();
+        let width = label_width + message_width;
+        let label = escape(&self.label);
+        let message = escape(&self.message);
+
+        let mut svg = String::new();
+        write!(
+            svg,
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}">"##
+        )
+        .unwrap();
+        write!(svg, "<title>{}: {}</title>", label, message).unwrap();
+        svg.push_str(concat!(
+            r##"<linearGradient id="s" x2="0" y2="100%">"##,
+            r##"<stop offset="0" stop-color="#bbb" stop-opacity=".1"/>"##,
+            r##"<stop offset="1" stop-opacity=".1"/>"##,
+            r##"</linearGradient>"##,
+        ));
+        write!(
+            svg,
+            r##"<clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>"##
+        )
+        .unwrap();
+        write!(
+            svg,
+            concat!(
+                r##"<g clip-path="url(#r)">"##,
+                r##"<rect width="{lw}" height="20" fill="#555"/>"##,
+                r##"<rect x="{lw}" width="{mw}" height="20" fill="{color}"/>"##,
+                r##"<rect width="{width}" height="20" fill="url(#s)"/>"##,
+                r##"</g>"##,
+            ),
+            lw = label_width,
+            mw = message_width,
+            color = self.color,
+            width = width,
+        )
+        .unwrap();
+        svg.push_str(r##"<g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">"##);
+        for (x, text) in [
+            (label_width / 2.0, &label),
+            (label_width + message_width / 2.0, &message),
+        ] {
+            write!(
+                svg,
+                concat!(
+                    r##"<text x="{x}" y="15" fill="#010101" fill-opacity=".3">{text}</text>"##,
+                    r##"<text x="{x}" y="14">{text}</text>"##,
+                ),
+                x = x,
+                text = text,
+            )
+            .unwrap();
+        }
+        svg.push_str("</g></svg>\n");
+        svg
+    }
+}
+
+/// Estimate the width in pixels of the text rendered in 11px Verdana,
+/// which is the font used in badges.
+fn text_width(text: &str) -> f64 {
+    // Advance widths of printable ASCII characters in Verdana,
+    // in font units of a 2048-unit em, starting from space.
+    #[rustfmt::skip]
+    const WIDTHS: [u16; 95] = [
+        720, 822, 940, 1716, 1303, 2222, 1493, 552, 913, 913, 1303, 1716, 745, 924, 745, 924, // ' '..'/'
+        1303, 1303, 1303, 1303, 1303, 1303, 1303, 1303, 1303, 1303, // '0'..'9'
+        924, 924, 1716, 1716, 1716, 1113, 2048, // ':'..'@'
+        1401, 1405, 1430, 1577, 1294, 1178, 1587, 1540, 862, 931, 1422, 1155, 1716, // 'A'..'M'
+        1532, 1612, 1235, 1612, 1424, 1401, 1266, 1499, 1401, 2023, 1404, 1254, 1403, // 'N'..'Z'
+        913, 924, 913, 1716, 1303, 1303, // '['..'`'
+        1229, 1276, 1067, 1276, 1208, 721, 1276, 1296, 562, 706, 1186, 562, 1992, // 'a'..'m'
+        1296, 1243, 1276, 1276, 874, 1067, 807, 1296, 1186, 1669, 1186, 1186, 1071, // 'n'..'z'
+        1300, 924, 1300, 1716, // '{'..'~'
+    ];
+    // Width of wide characters like 'M', used for anything outside ASCII.
+    const FALLBACK: u16 = 1716;
+    let units: u32 = text
+        .chars()
+        .map(|c| match c {
+            ' '..='~' => WIDTHS[c as usize - ' ' as usize],
+            _ => FALLBACK,
+        })
+        .map(u32::from)
+        .sum();
+    f64::from(units) * 11.0 / 2048.0
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::output::Stabilization;
+    use crate::data::Issue;
+
+    fn stabilized(state: VersionState) -> Item {
+        let mut item = Item::new_for_test("a", "A");
+        item.stabilized = Some(Stabilization {
+            state,
+            version: "1.39".to_string(),
+            release_date: None,
+            pr: Issue {
+                number: 1,
+                title: "Stabilize".to_string(),
+                open: false,
+                pull_request: None,
+            },
+        });
+        item
+    }
+
+    fn linked() -> Item {
+        let mut item = stabilized(VersionState::Stable);
+        item.link = Some(crate::data::Link {
+            text: "crate".to_string(),
+            url: "https://crates.io/".to_string(),
+        });
+        item
+    }
+
+    #[test]
+    fn item_messages() {
+        let cases = [
+            (stabilized(VersionState::Stable), "stable 1.39", GREEN),
+            (stabilized(VersionState::Beta), "beta 1.39", YELLOW),
+            (stabilized(VersionState::Nightly), "nightly", PURPLE),
+            (Item::new_for_test("a", "A"), "not stabilized", GREY),
+            (linked(), "workaround", GREY),
+        ];
+        for (item, message, color) in cases {
+            let badge = item_badge(&item);
+            assert_eq!(badge.label, "rust");
+            assert_eq!(badge.message, message);
+            assert_eq!(badge.color, color);
+        }
+    }
+
+    #[test]
+    fn section_message() {
+        let items = [
+            stabilized(VersionState::Stable),
+            stabilized(VersionState::Beta),
+        ];
+        let badge = section_badge("async_basics", &items);
+        assert_eq!(badge.label, "async basics");
+        assert_eq!(badge.message, "1/2 stabilized");
+        assert_eq!(badge.color, BLUE);
+        let badge = section_badge("async_basics", &items[..1]);
+        assert_eq!(badge.message, "1/1 stabilized");
+        assert_eq!(badge.color, GREEN);
+    }
+
+    #[test]
+    fn text_widths() {
+        assert_eq!(text_width(""), 0.0);
+        // An em of 'M' is a bit less than the font size.
+        assert_eq!(text_width("M"), 1716.0 * 11.0 / 2048.0);
+        assert_eq!(text_width("é"), text_width("M"));
+        assert!(text_width("rust") < text_width("rustc"));
+    }
+
+    #[test]
+    fn render() {
+        let badge = Badge {
+            label: "rust".to_string(),
+            message: "<&>".to_string(),
+            color: GREEN,
+        };
+        let svg = badge.render();
+        let label_width = (text_width("rust") + 10.0).round();
+        let message_width = (text_width("<&>") + 10.0).round();
+        let width = label_width + message_width;
+        assert!(svg.starts_with(&format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="20""#,
+            width
+        )));
+        assert!(svg.contains("<title>rust: &lt;&amp;&gt;</title>"));
+        assert!(svg.contains(&format!(
+            r##"<rect x="{}" width="{}" height="20" fill="#4c1"/>"##,
+            label_width, message_width
+        )));
+        assert!(svg.contains(&format!(
+            r#"<text x="{}" y="14">rust</text>"#,
+            label_width / 2.0
+        )));
+        assert!(!svg.contains("<&>"));
+        assert!(svg.ends_with("</svg>\n"));
+    }
+}
@@ -0,0 +1,294 @@
+//! Trends of issue counts under labels, rendered as inline SVG.
+
+use crate::history::{LabelCounts, Snapshot};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::sync::Arc;
+use tera::{Tera, Value};
+
+const OPEN_COLOR: &str = "#e05d44";
+const CLOSED_COLOR: &str = "#4c1";
+
+type Point = (DateTime<Utc>, LabelCounts);
+
+/// Register `sparkline` and `trend_chart` functions, which render the
+/// history of the label given as `label` argument, or an empty string if
+/// there isn't enough history for a trend.
+pub fn register(tera: &mut Tera, history: &[Snapshot]) {
+    let mut series: BTreeMap<String, Vec<Point>> = BTreeMap::new();
+    for snapshot in history {
+        for (label, counts) in snapshot.labels.iter() {
+            let points = series.entry(label.clone()).or_default();
+            points.push((snapshot.time, *counts));
+        }
+    }
+    let series = Arc::new(series);
+    let sparkline_series = series.clone();
+    tera.register_function("sparkline", move |args: &HashMap<String, Value>| {
+        render_with(&sparkline_series, args, render_sparkline)
+    });
+    tera.register_function("trend_chart", move |args: &HashMap<String, Value>| {
+        render_with(&series, args, render_chart)
+    });
+}
+
+fn render_with(
+    series: &BTreeMap<String, Vec<Point>>,
+    args: &HashMap<String, Value>,
+    render: fn(&str, &[Point]) -> String,
+) -> tera::Result<Value> {
+    let label = match args.get("label") {
+        Some(Value::String(label)) => label,
+        _ => return Err("`label` argument must be a string".into()),
+    };
+    match series.get(label) {
+        Some(points) if points.len() >= 2 => Ok(render(label, points).into()),
+        _ => Ok("".into()),
+    }
+}
+
+/// Map points into coordinates within the given box, with time on the
+/// x-axis, and the open and closed counts on the y-axis.
+struct Scale {
+    start: DateTime<Utc>,
+    duration: f64,
+    max: usize,
+    left: f64,
+    top: f64,
+    width: f64,
+    height: f64,
+}
+
+impl Scale {
+    fn new(points: &[Point], left: f64, top: f64, width: f64, height: f64) -> Self {
+        let start = points[0].0;
+        let end = points[points.len() - 1].0;
+        let max = points
+            .iter()
+            .map(|(_, counts)| counts.open.max(counts.closed))
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        Scale {
+            start,
+            duration: (end - start).num_seconds() as f64,
+            max,
+            left,
+            top,
+            width,
+            height,
+        }
+    }
+
+    fn x(&self, time: DateTime<Utc>) -> f64 {
+        if self.duration <= 0.0 {
+            return self.left + self.width;
+        }
+        let offset = (time - self.start).num_seconds() as f64;
+        self.left + offset / self.duration * self.width
+    }
+
+    fn y(&self, count: usize) -> f64 {
+        self.top + self.height - count as f64 / self.max as f64 * self.height
+    }
+
+    fn polyline(&self, points: &[Point], count: fn(&LabelCounts) -> usize, color: &str) -> String {
+        let mut coords = String::new();
+        for (time, counts) in points {
+            let (x, y) = (self.x(*time), self.y(count(counts)));
+            write!(coords, "{:.1},{:.1} ", x, y).unwrap();
+        }
+        format!(
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="1.5"/>"#,
+            coords.trim_end(),
+            color
+        )
+    }
+}
+
+fn render_sparkline(label: &str, points: &[Point]) -> String {
+    const WIDTH: f64 = 100.0;
+    const HEIGHT: f64 = 20.0;
+    let scale = Scale::new(points, 1.0, 1.0, WIDTH - 2.0, HEIGHT - 2.0);
+    let title = describe(label, points);
+    let mut svg = String::new();
+    write!(
+        svg,
+        r#"<svg class="sparkline" xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" role="img" aria-label="{}">"#,
+        WIDTH, HEIGHT, title
+    )
+    .unwrap();
+    write!(svg, "<title>{}</title>", title).unwrap();
+    svg.push_str(&scale.polyline(points, |c| c.closed, CLOSED_COLOR));
+    svg.push_str(&scale.polyline(points, |c| c.open, OPEN_COLOR));
+    svg.push_str("</svg>");
+    svg
+}
+
+fn render_chart(label: &str, points: &[Point]) -> String {
+    const WIDTH: f64 = 600.0;
+    const HEIGHT: f64 = 200.0;
+    const LEFT: f64 = 40.0;
+    const TOP: f64 = 20.0;
+    const BOTTOM: f64 = 20.0;
+    const RIGHT: f64 = 10.0;
+    let scale = Scale::new(
+        points,
+        LEFT,
+        TOP,
+        WIDTH - LEFT - RIGHT,
+        HEIGHT - TOP - BOTTOM,
+    );
+    let title = describe(label, points);
+    let (start, end) = (points[0].0, points[points.len() - 1].0);
+    let mut svg = String::new();
+    write!(
+        svg,
+        r#"<svg class="trend-chart" xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {} {}" role="img" aria-label="{}">"#,
+        WIDTH, HEIGHT, title
+    )
+    .unwrap();
+    write!(svg, "<title>{}</title>", title).unwrap();
+    svg.push_str(r##"<g font-family="sans-serif" font-size="11" fill="#666">"##);
+    // Horizontal grid lines at zero and the maximum count.
+    for count in [0, scale.max] {
+        let y = scale.y(count);
+        write!(
+            svg,
+            concat!(
+                r##"<line x1="{left}" y1="{y:.1}" x2="{right}" y2="{y:.1}" stroke="#ddd"/>"##,
+                r##"<text x="{x}" y="{text_y:.1}" text-anchor="end">{count}</text>"##,
+            ),
+            left = LEFT,
+            right = WIDTH - RIGHT,
+            y = y,
+            x = LEFT - 4.0,
+            text_y = y + 4.0,
+            count = count,
+        )
+        .unwrap();
+    }
+    for (time, anchor) in [(start, "start"), (end, "end")] {
+        write!(
+            svg,
+            r#"<text x="{:.1}" y="{}" text-anchor="{}">{}</text>"#,
+            scale.x(time),
+            HEIGHT - 4.0,
+            anchor,
+            time.format("%Y-%m-%d")
+        )
+        .unwrap();
+    }
+    for (i, (name, color)) in [("open", OPEN_COLOR), ("closed", CLOSED_COLOR)]
+        .iter()
+        .enumerate()
+    {
+        let x = LEFT + i as f64 * 60.0;
+        write!(
+            svg,
+            r#"<rect x="{}" y="4" width="10" height="10" fill="{}"/><text x="{}" y="13">{}</text>"#,
+            x,
+            color,
+            x + 14.0,
+            name
+        )
+        .unwrap();
+    }
+    svg.push_str("</g>");
+    svg.push_str(&scale.polyline(points, |c| c.closed, CLOSED_COLOR));
+    svg.push_str(&scale.polyline(points, |c| c.open, OPEN_COLOR));
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Describe the latest counts for the title of a chart.
+fn describe(label: &str, points: &[Point]) -> String {
+    let (_, counts) = points[points.len() - 1];
+    format!(
+        "{}: {} open, {} closed",
+        tera::escape_html(label),
+        counts.open,
+        counts.closed
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tera::Context;
+
+    fn point(day: u32, open: usize, closed: usize) -> Point {
+        let time = Utc.with_ymd_and_hms(2020, 1, day, 0, 0, 0).unwrap();
+        (time, LabelCounts { open, closed })
+    }
+
+    fn snapshot(id: u32, point: Point) -> Snapshot {
+        Snapshot {
+            id,
+            time: point.0,
+            labels: BTreeMap::from([("A-a".to_string(), point.1)]),
+            items: BTreeMap::new(),
+        }
+    }
+
+    fn render(history: &[Snapshot], function: &str) -> String {
+        let mut tera = Tera::default();
+        register(&mut tera, history);
+        let template = format!(r#"{{{{ {}(label="A-a") }}}}"#, function);
+        tera.render_str(&template, &Context::new()).unwrap()
+    }
+
+    #[test]
+    fn needs_two_points() {
+        for function in ["sparkline", "trend_chart"] {
+            assert_eq!(render(&[], function), "");
+            assert_eq!(render(&[snapshot(1, point(1, 1, 1))], function), "");
+            let history = [snapshot(1, point(1, 1, 1)), snapshot(2, point(2, 2, 1))];
+            assert!(render(&history, function).starts_with("<svg"));
+        }
+    }
+
+    #[test]
+    fn scale_single_point() {
+        let points = [point(1, 3, 1)];
+        let scale = Scale::new(&points, 10.0, 0.0, 100.0, 50.0);
+        // Without a time span, points are at the right edge.
+        assert_eq!(scale.x(points[0].0), 110.0);
+        assert_eq!(scale.y(3), 0.0);
+        assert_eq!(scale.y(0), 50.0);
+    }
+
+    #[test]
+    fn scale_equal_counts() {
+        let points = [point(1, 2, 2), point(3, 2, 2)];
+        let scale = Scale::new(&points, 0.0, 0.0, 100.0, 50.0);
+        assert_eq!(scale.max, 2);
+        assert_eq!(scale.x(points[0].0), 0.0);
+        assert_eq!(scale.x(point(2, 0, 0).0), 50.0);
+        assert_eq!(scale.x(points[1].0), 100.0);
+        assert_eq!(scale.y(2), 0.0);
+        let line = scale.polyline(&points, |c| c.open, OPEN_COLOR);
+        assert!(line.contains(r#"points="0.0,0.0 100.0,0.0""#), "{}", line);
+    }
+
+    #[test]
+    fn scale_zero_counts() {
+        let points = [point(1, 0, 0), point(2, 0, 0)];
+        let scale = Scale::new(&points, 0.0, 0.0, 100.0, 50.0);
+        assert_eq!(scale.max, 1);
+        assert_eq!(scale.y(0), 50.0);
+        let svg = render_chart("A-a", &points);
+        assert!(!svg.contains("NaN"), "{}", svg);
+        assert!(svg.contains("A-a: 0 open, 0 closed"));
+    }
+
+    #[test]
+    fn escapes_label() {
+        let points = [point(1, 1, 0), point(2, 1, 1)];
+        let svg = render_sparkline("<a>", &points);
+        assert!(svg.contains("&lt;a&gt;: 1 open, 1 closed"), "{}", svg);
+        assert!(!svg.contains("<a>"));
+    }
+}
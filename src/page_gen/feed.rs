@@ -0,0 +1,148 @@
+use super::{item_path, PageGenData, SITE_URL};
+use crate::changes::{Change, ChangeKind, StabilizationState};
+use crate::data::output::Item;
+use crate::posts::Post;
+use anyhow::{Context as _, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use tera::{Context, Tera};
+
+pub const FEED_FILE: &str = "feed.xml";
+const TAG_PREFIX: &str = "tag:areweasyncyet.rs,2019:";
+
+#[derive(Serialize)]
+struct Entry {
+    id: String,
+    title: String,
+    updated: DateTime<Utc>,
+    link: String,
+    summary: String,
+}
+
+pub fn render(tera: &Tera, data: &PageGenData) -> Result<String> {
+    let items: HashMap<_, _> = data
+        .all_items()
+        .into_iter()
+        .map(|node| (node.item.id.as_str(), node.item))
+        .collect();
+
+    let mut entries = Vec::new();
+    for item in items.values() {
+        if let Some(entry) = stabilization_entry(item, &data.changes) {
+            entries.push(entry);
+        }
+    }
+    for change in data.changes.iter() {
+        // Items may have been removed since the change was recorded.
+        if let Some(item) = items.get(change.item.as_str()) {
+            entries.push(change_entry(item, &change.kind, change.time));
+        }
+    }
+    for post in data.posts.iter() {
+        entries.push(post_entry(post)?);
+    }
+    entries.sort_by(|a, b| b.updated.cmp(&a.updated).then_with(|| a.id.cmp(&b.id)));
+
+    let mut context = Context::new();
+    context.insert("site_url", SITE_URL);
+    context.insert("updated", &data.time);
+    context.insert("entries", &entries);
+    Ok(tera.render(FEED_FILE, &context)?)
+}
+
+fn stabilization_entry(item: &Item, changes: &[Change]) -> Option<Entry> {
+    let stabilized = item.stabilized.as_ref()?;
+    // Date the entry when the stabilization was first detected, so that it
+    // stays the same across builds, or at the release date for ones detected
+    // before the change log was kept, if there's one.
+    let detected = changes
+        .iter()
+        .filter(|change| change.item == item.id)
+        .filter(|change| match &change.kind {
+            ChangeKind::Stabilization { to: Some(to), .. } => to.version == stabilized.version,
+            _ => false,
+        })
+        .map(|change| change.time)
+        .min();
+    let updated = detected.or_else(|| stabilized.release_date.map(to_datetime))?;
+    Some(Entry {
+        id: format!("{}stabilization/{}", TAG_PREFIX, item.id),
+        title: format!("{} stabilized in Rust {}", item.title, stabilized.version),
+        updated,
+        link: format!(
+            "https://github.com/rust-lang/rust/pull/{}",
+            stabilized.pr.number
+        ),
+        summary: format!(
+            "Stabilized in #{}: {}, currently in {}.",
+            stabilized.pr.number,
+            stabilized.pr.title,
+            stabilized.state.name()
+        ),
+    })
+}
+
+fn change_entry(item: &Item, kind: &ChangeKind, time: DateTime<Utc>) -> Entry {
+    let id = format!("{}change/{}/{}", TAG_PREFIX, item.id, time.timestamp());
+    let (id, title, summary) = match kind {
+        ChangeKind::Stabilization { from, to } => {
+            let title = match to {
+                Some(to) => format!(
+                    "{} is in {} as of Rust {}",
+                    item.title,
+                    to.state.name(),
+                    to.version
+                ),
+                None => format!("{} is no longer stabilized", item.title),
+            };
+            let from = from.as_ref().map_or("not stabilized".to_string(), describe);
+            let to = to.as_ref().map_or("not stabilized".to_string(), describe);
+            (id, title, format!("Changed from {} to {}.", from, to))
+        }
+        ChangeKind::TrackingClosed { issue } => (
+            format!("{}/tracking-closed", id),
+            format!("Tracking issue of {} closed", item.title),
+            format!("Tracking issue #{} has been closed.", issue),
+        ),
+        ChangeKind::NewIssue {
+            label,
+            issue,
+            title,
+        } => (
+            format!("{}/issue/{}", id, issue),
+            format!("New issue under {}: {}", label, title),
+            format!(
+                "Issue #{} has been added to issues related to {}.",
+                issue, item.title
+            ),
+        ),
+    };
+    Entry {
+        id,
+        title,
+        updated: time,
+        link: format!("{}{}", SITE_URL, item_path(&item.id)),
+        summary,
+    }
+}
+
+fn post_entry(post: &Post) -> Result<Entry> {
+    let date = NaiveDate::parse_from_str(&post.date, "%Y-%m-%d")
+        .with_context(|| format!("invalid date of post {}", post.url))?;
+    Ok(Entry {
+        id: format!("{}post/{}", TAG_PREFIX, post.url),
+        title: post.title.clone(),
+        updated: to_datetime(date),
+        link: post.url.clone(),
+        summary: post.title.clone(),
+    })
+}
+
+fn to_datetime(date: NaiveDate) -> DateTime<Utc> {
+    DateTime::from_utc(date.and_hms_opt(0, 0, 0).unwrap(), Utc)
+}
+
+fn describe(state: &StabilizationState) -> String {
+    format!("{} {}", state.state.name(), state.version)
+}
@@ -1,30 +1,397 @@
-use crate::data::output::Item;
+use crate::assets;
+use crate::changes::Change;
+use crate::data::output::{Item, VersionState};
+use crate::history::Snapshot;
 use crate::posts::Post;
 use anyhow::Result;
-use chrono::Utc;
-use std::collections::HashMap;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
+use std::path::Path;
 use tera::{Context, Tera};
 
+pub mod api;
+mod badge;
+mod chart;
+mod feed;
 mod filters;
 
 const INDEX_FILE: &str = "index.html";
+const ITEM_FILE: &str = "item.html";
+const TIMELINE_FILE: &str = "timeline.html";
+pub const SITE_URL: &str = "https://areweasyncyet.rs/";
+/// Number of days changes are highlighted on the index page.
+const RECENT_DAYS: i64 = 14;
 
 pub struct PageGenData {
-    pub items: HashMap<String, Vec<Item>>,
+    pub items: BTreeMap<String, Vec<Item>>,
     pub posts: Vec<Post>,
+    pub changes: Vec<Change>,
+    /// Snapshots recorded before this build, oldest first.
+    pub history: Vec<Snapshot>,
+    /// Snapshot of this build, unless it repeats the last one. It's only
+    /// recorded after generation, so that the output doesn't depend on
+    /// how many times the same inputs were built.
+    pub snapshot: Option<Snapshot>,
+    pub time: DateTime<Utc>,
 }
 
-pub fn generate(data: &PageGenData) -> Result<()> {
-    let mut tera = Tera::new("templates/**/*.html")?;
+/// An item with its position in the item tree.
+#[derive(Clone, Copy)]
+struct ItemNode<'a> {
+    section: &'a str,
+    parent: Option<&'a Item>,
+    item: &'a Item,
+}
+
+impl PageGenData {
+    /// List all items including deps, each item followed by its deps.
+    fn all_items(&self) -> Vec<ItemNode<'_>> {
+        fn collect<'a>(
+            result: &mut Vec<ItemNode<'a>>,
+            section: &'a str,
+            parent: Option<&'a Item>,
+            items: &'a [Item],
+        ) {
+            for item in items {
+                result.push(ItemNode {
+                    section,
+                    parent,
+                    item,
+                });
+                collect(result, section, Some(item), &item.deps);
+            }
+        }
+
+        let mut result = Vec::new();
+        for (section, items) in self.items.iter() {
+            collect(&mut result, section, None, items);
+        }
+        result
+    }
+}
+
+pub fn generate(data: &PageGenData, out_dir: &Path, templates: Option<&Path>) -> Result<()> {
+    let mut tera = Tera::default();
+    tera.add_raw_templates(assets::templates(templates)?)?;
     tera.register_filter("codify", filters::codify);
     tera.register_filter("pr_url", filters::pr_url);
     tera.register_filter("issue_url", filters::issue_url);
+    chart::register(&mut tera, &data.history);
     let mut context = Context::new();
     context.insert("items", &data.items);
     context.insert("posts", &data.posts);
-    context.insert("time", &Utc::now().to_rfc2822());
+    let recent_changes = recent_changes(data);
+    let changed: BTreeSet<_> = recent_changes
+        .iter()
+        .map(|change| change.item.id.as_str())
+        .collect();
+    context.insert("recent_changes", &recent_changes);
+    context.insert("recent_days", &RECENT_DAYS);
+    context.insert("changed", &changed);
+    context.insert("time", &data.time.to_rfc2822());
     let html = tera.render(INDEX_FILE, &context)?;
-    fs::write(super::OUT_DIR.join(INDEX_FILE), html)?;
+    fs::write(out_dir.join(INDEX_FILE), html)?;
+    generate_item_pages(&tera, data, out_dir)?;
+    generate_timeline(&tera, data, out_dir)?;
+    let feed = feed::render(&tera, data)?;
+    fs::write(out_dir.join(feed::FEED_FILE), feed)?;
+    api::generate(data, out_dir)?;
+    badge::generate(data, out_dir)?;
     Ok(())
 }
+
+fn generate_item_pages(tera: &Tera, data: &PageGenData, out_dir: &Path) -> Result<()> {
+    for node in data.all_items() {
+        let item = node.item;
+        let posts: Vec<_> = data
+            .posts
+            .iter()
+            .filter(|post| post.items.contains(&item.id))
+            .collect();
+        let changes: Vec<_> = data
+            .changes
+            .iter()
+            .filter(|change| change.item == item.id)
+            .map(|change| ChangeView::new(change, item))
+            .collect();
+        let mut context = Context::new();
+        context.insert("root", "../../");
+        context.insert("item", item);
+        context.insert("section", node.section);
+        context.insert("parent", &node.parent);
+        context.insert("posts", &posts);
+        context.insert("changes", &changes);
+        context.insert("time", &data.time.to_rfc2822());
+        let html = tera.render(ITEM_FILE, &context)?;
+        let dir = out_dir.join(item_path(&item.id));
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(INDEX_FILE), html)?;
+    }
+    Ok(())
+}
+
+/// A change with its item and description for rendering.
+#[derive(Serialize)]
+struct ChangeView<'a> {
+    item: &'a Item,
+    date: String,
+    description: String,
+}
+
+impl<'a> ChangeView<'a> {
+    fn new(change: &Change, item: &'a Item) -> Self {
+        ChangeView {
+            item,
+            date: change.time.format("%Y-%m-%d").to_string(),
+            description: change.kind.describe(),
+        }
+    }
+}
+
+/// Changes within the last `RECENT_DAYS` days, newest first.
+fn recent_changes(data: &PageGenData) -> Vec<ChangeView<'_>> {
+    let items: HashMap<_, _> = data
+        .all_items()
+        .into_iter()
+        .map(|node| (node.item.id.as_str(), node.item))
+        .collect();
+    let since = data.time - Duration::days(RECENT_DAYS);
+    data.changes
+        .iter()
+        .take_while(|change| change.time >= since)
+        // Items may have been removed since the change was recorded.
+        .filter_map(|change| Some(ChangeView::new(change, items.get(change.item.as_str())?)))
+        .collect()
+}
+
+/// Items stabilized in a single Rust release.
+#[derive(Serialize)]
+struct Release<'a> {
+    version: &'a str,
+    release_date: Option<NaiveDate>,
+    state: VersionState,
+    items: Vec<&'a Item>,
+}
+
+fn generate_timeline(tera: &Tera, data: &PageGenData, out_dir: &Path) -> Result<()> {
+    let mut releases: Vec<Release> = Vec::new();
+    let mut upcoming = Vec::new();
+    let mut nodes = data.all_items();
+    nodes.sort_by_key(|node| {
+        let stabilized = node.item.stabilized.as_ref();
+        stabilized.map(|s| (s.release_date, s.version.as_str()))
+    });
+    for node in nodes.into_iter().rev() {
+        let item = node.item;
+        let stabilized = match &item.stabilized {
+            Some(stabilized) => stabilized,
+            None => {
+                // Items with only a link are not something to be stabilized.
+                if item.link.is_none() {
+                    upcoming.push(item);
+                }
+                continue;
+            }
+        };
+        match releases.last_mut() {
+            Some(release) if release.version == stabilized.version => release.items.push(item),
+            _ => releases.push(Release {
+                version: &stabilized.version,
+                release_date: stabilized.release_date,
+                state: stabilized.state,
+                items: vec![item],
+            }),
+        }
+    }
+
+    let mut context = Context::new();
+    context.insert("releases", &releases);
+    context.insert("upcoming", &upcoming);
+    context.insert("time", &data.time.to_rfc2822());
+    let html = tera.render(TIMELINE_FILE, &context)?;
+    fs::write(out_dir.join(TIMELINE_FILE), html)?;
+    Ok(())
+}
+
+/// Path of the detail page of the given item, relative to the site root.
+pub fn item_path(id: &str) -> String {
+    format!("items/{}/", id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::changes::ChangeKind;
+    use crate::data::output::{self, Stabilization};
+    use crate::data::{Issue, Link};
+    use chrono::TimeZone;
+    use serde_json::Value;
+
+    fn issue(number: u32, title: &str) -> Issue {
+        Issue {
+            number,
+            title: title.to_string(),
+            open: false,
+            pull_request: None,
+        }
+    }
+
+    fn stabilized(id: &str, title: &str, version: &str, state: VersionState) -> Item {
+        let mut item = Item::new_for_test(id, title);
+        item.stabilized = Some(Stabilization {
+            state,
+            version: version.to_string(),
+            release_date: output::release_date(version),
+            pr: issue(1, &format!("Stabilize {}", title)),
+        });
+        item
+    }
+
+    fn change(item: &str, days: i64) -> Change {
+        Change {
+            item: item.to_string(),
+            time: time() - Duration::days(days),
+            kind: ChangeKind::TrackingClosed { issue: 2 },
+        }
+    }
+
+    fn time() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap()
+    }
+
+    /// Items a to e in the first of the sections on the index page,
+    /// with b as a dep of a.
+    fn data() -> PageGenData {
+        let mut a = stabilized("a", "A", "1.39", VersionState::Stable);
+        a.deps
+            .push(stabilized("b", "B", "1.80", VersionState::Nightly));
+        let c = stabilized("c", "C", "1.39", VersionState::Stable);
+        let d = Item::new_for_test("d", "D");
+        let mut e = Item::new_for_test("e", "E");
+        e.link = Some(Link {
+            text: "crate".to_string(),
+            url: "https://crates.io/".to_string(),
+        });
+        let mut items = BTreeMap::new();
+        items.insert("async_blockers".to_string(), vec![a, c, d, e]);
+        items.insert("async_extensions".to_string(), Vec::new());
+        PageGenData {
+            items,
+            posts: vec![Post {
+                title: "Post about A".to_string(),
+                date: "2024-01-01".to_string(),
+                url: "https://example.com/a".to_string(),
+                items: vec!["a".to_string()],
+            }],
+            changes: vec![change("b", 1), change("removed", 2), change("a", 15)],
+            history: Vec::new(),
+            snapshot: None,
+            time: time(),
+        }
+    }
+
+    fn read(dir: &Path, file: &str) -> String {
+        fs::read_to_string(dir.join(file)).unwrap()
+    }
+
+    fn position(text: &str, pattern: &str) -> usize {
+        text.find(pattern)
+            .unwrap_or_else(|| panic!("`{}` not found", pattern))
+    }
+
+    #[test]
+    fn all_items_in_order() {
+        let data = data();
+        let nodes = data.all_items();
+        let ids: Vec<_> = nodes.iter().map(|node| node.item.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "c", "d", "e"]);
+        let parents: Vec<_> = nodes
+            .iter()
+            .map(|node| node.parent.map(|parent| parent.id.as_str()))
+            .collect();
+        assert_eq!(parents, [None, Some("a"), None, None, None]);
+    }
+
+    #[test]
+    fn recent_changes_skip_old_and_removed_items() {
+        let data = data();
+        let changes = recent_changes(&data);
+        let ids: Vec<_> = changes
+            .iter()
+            .map(|change| change.item.id.as_str())
+            .collect();
+        assert_eq!(ids, ["b"]);
+        assert_eq!(changes[0].date, "2024-05-31");
+    }
+
+    #[test]
+    fn item_pages() {
+        let dir = tempfile::tempdir().unwrap();
+        generate(&data(), dir.path(), None).unwrap();
+        for id in ["a", "b", "c", "d", "e"] {
+            assert!(dir.path().join(item_path(id)).join(INDEX_FILE).exists());
+        }
+        let a = read(dir.path(), "items/a/index.html");
+        assert!(a.contains(r#"items/b/">B</a>"#));
+        assert!(a.contains("Post about A"));
+        assert!(a.contains("on 2019-11-07"));
+        // The change is too old to be on the index page, but is in the history.
+        assert!(a.contains("2024-05-17: "));
+        let b = read(dir.path(), "items/b/index.html");
+        assert!(b.contains("<h2>Dependents</h2>"));
+        assert!(b.contains(r#"items/a/">A</a>"#));
+        assert!(!b.contains("Post about A"));
+    }
+
+    #[test]
+    fn timeline() {
+        let dir = tempfile::tempdir().unwrap();
+        generate(&data(), dir.path(), None).unwrap();
+        let html = read(dir.path(), TIMELINE_FILE);
+        let upcoming = position(&html, "<h2>Upcoming</h2>");
+        let nightly = position(&html, r#"<h2 id="rust-1.80">"#);
+        let stable = position(&html, r#"<h2 id="rust-1.39">"#);
+        assert!(upcoming < nightly && nightly < stable);
+        assert!(html.contains("Expected on 2024-07-25"));
+        assert!(html.contains("Released on 2019-11-07"));
+        // Items of the same release are grouped under one heading.
+        assert_eq!(html.matches(r#"<h2 id="rust-1.39">"#).count(), 1);
+        let release = &html[stable..];
+        assert!(release.contains(r#"href="items/a/""#));
+        assert!(release.contains(r#"href="items/c/""#));
+        // Items with only a link are not upcoming.
+        assert!(html[upcoming..nightly].contains(r#"href="items/d/""#));
+        assert!(!html.contains(r#"href="items/e/""#));
+    }
+
+    #[test]
+    fn api() {
+        let dir = tempfile::tempdir().unwrap();
+        generate(&data(), dir.path(), None).unwrap();
+        let items: Value = serde_json::from_str(&read(dir.path(), api::ITEMS_FILE)).unwrap();
+        assert_eq!(items["version"], api::VERSION);
+        let items = items["items"].as_array().unwrap();
+        let ids: Vec<_> = items
+            .iter()
+            .map(|item| item["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(ids, ["a", "b", "c", "d", "e"]);
+        assert_eq!(items[0]["section"], "async_blockers");
+        assert_eq!(items[0]["deps"], serde_json::json!(["b"]));
+        assert_eq!(items[1]["parent"], "a");
+        assert_eq!(items[0]["stabilized"]["release_date"], "2019-11-07");
+        assert_eq!(
+            items[0]["stabilized"]["pr"]["url"],
+            "https://github.com/rust-lang/rust/issues/1"
+        );
+        let item: Value = serde_json::from_str(&read(dir.path(), "api/v1/items/b.json")).unwrap();
+        assert_eq!(item["item"], items[1]);
+        let changes: Value = serde_json::from_str(&read(dir.path(), api::CHANGES_FILE)).unwrap();
+        assert_eq!(changes["changes"].as_array().unwrap().len(), 3);
+        let posts: Value = serde_json::from_str(&read(dir.path(), "api/v1/posts.json")).unwrap();
+        assert_eq!(posts["posts"][0]["items"], serde_json::json!(["a"]));
+        assert_eq!(read(dir.path(), api::HISTORY_FILE), "");
+    }
+}
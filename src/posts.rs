@@ -1,13 +1,20 @@
 use crate::POSTS_FILE;
 use anyhow::Result;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fs::File;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct Post {
     pub title: String,
+    /// Publish date in the form of `YYYY-MM-DD`.
+    #[schemars(regex(pattern = r"^[0-9]{4}-[0-9]{2}-[0-9]{2}$"))]
     pub date: String,
     pub url: String,
+    /// Ids of items in data.yml this post is about.
+    #[serde(default)]
+    pub items: Vec<String>,
 }
 
 pub fn load_posts() -> Result<Vec<Post>> {
@@ -0,0 +1,137 @@
+//! Files published by a previous build, which state carried across builds
+//! is read back from.
+
+use anyhow::{Context, Result};
+use log::info;
+use reqwest::Client;
+use serde::Deserialize;
+use std::convert::Infallible;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Where the previous build is read from, either an output directory or
+/// the URL of a deployed site.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PreviousSource {
+    Dir(PathBuf),
+    Url(String),
+}
+
+impl FromStr for PreviousSource {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with("http://") || s.starts_with("https://") {
+            let mut url = s.to_string();
+            if !url.ends_with('/') {
+                url.push('/');
+            }
+            Ok(PreviousSource::Url(url))
+        } else {
+            Ok(PreviousSource::Dir(PathBuf::from(s)))
+        }
+    }
+}
+
+impl Display for PreviousSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PreviousSource::Dir(dir) => write!(f, "{}", dir.display()),
+            PreviousSource::Url(url) => f.write_str(url),
+        }
+    }
+}
+
+impl PreviousSource {
+    /// Read the given file of the previous build.
+    ///
+    /// A file missing from a directory means there was no previous build
+    /// there, and gives `None`. Any failure to fetch from a URL is an error
+    /// instead, including 404, so that an unreachable site is never taken
+    /// for one without history.
+    pub async fn read(&self, client: &Client, file: &str) -> Result<Option<String>> {
+        match self {
+            PreviousSource::Dir(dir) => {
+                let path = dir.join(file);
+                match fs::read_to_string(&path) {
+                    Ok(content) => Ok(Some(content)),
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+                    Err(e) => Err(e).with_context(|| format!("failed to read {}", path.display())),
+                }
+            }
+            PreviousSource::Url(url) => {
+                let url = format!("{}{}", url, file);
+                info!("fetching {}...", url);
+                let result = async {
+                    let resp = client.get(&url).send().await?.error_for_status()?;
+                    resp.text().await
+                };
+                let content = result
+                    .await
+                    .with_context(|| format!("failed to fetch {}", url))?;
+                Ok(Some(content))
+            }
+        }
+    }
+
+    /// Read and parse the given JSON file of the previous build.
+    pub async fn read_json<T: for<'de> Deserialize<'de>>(
+        &self,
+        client: &Client,
+        file: &str,
+    ) -> Result<Option<T>> {
+        match self.read(client, file).await? {
+            Some(content) => serde_json::from_str(&content)
+                .map(Some)
+                .with_context(|| format!("failed to parse {} of {}", file, self)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_source() {
+        assert_eq!(
+            "_site".parse(),
+            Ok(PreviousSource::Dir(PathBuf::from("_site")))
+        );
+        assert_eq!(
+            "https://areweasyncyet.rs".parse(),
+            Ok(PreviousSource::Url("https://areweasyncyet.rs/".to_string()))
+        );
+        assert_eq!(
+            "http://localhost:8000/".parse(),
+            Ok(PreviousSource::Url("http://localhost:8000/".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn read_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.json"), r#"{"a": 1}"#).unwrap();
+        fs::write(dir.path().join("b.json"), "{").unwrap();
+        let source = PreviousSource::Dir(dir.path().to_path_buf());
+        let client = Client::new();
+        let a = source.read_json::<serde_json::Value>(&client, "a.json");
+        assert_eq!(a.await.unwrap(), Some(serde_json::json!({"a": 1})));
+        let b = source.read_json::<serde_json::Value>(&client, "b.json");
+        assert!(b.await.is_err());
+        let c = source.read_json::<serde_json::Value>(&client, "c.json");
+        assert_eq!(c.await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn unreachable_url_is_error() {
+        // Nothing listens on port 9 (discard) of localhost.
+        let source: PreviousSource = "http://127.0.0.1:9/".parse().unwrap();
+        let result = source.read(&Client::new(), "a.json").await;
+        assert!(result.is_err());
+    }
+}
@@ -2,7 +2,7 @@ use self::query::QueryRepositoryIssueOrPullRequest as IssueOrPr;
 use self::query::{IssueState, PullRequestState};
 use self::query::{ResponseData, Variables};
 use super::{GitHubQuery, Repo};
-use crate::data::{Issue, IssueId};
+use crate::data::{Issue, IssueId, PullRequest};
 use graphql_client::GraphQLQuery;
 use log::info;
 use matches::matches;
@@ -34,11 +34,16 @@ impl GitHubQuery<'_> {
                 number,
                 title: issue.title,
                 open: matches!(issue.issue_state, IssueState::OPEN),
+                pull_request: None,
             }),
             IssueOrPr::PullRequest(pr) => Ok(Issue {
                 number,
                 title: pr.title,
                 open: matches!(pr.pr_state, PullRequestState::OPEN),
+                pull_request: Some(PullRequest {
+                    merged: pr.merged,
+                    milestone: pr.milestone.map(|milestone| milestone.title),
+                }),
             }),
         }
     }
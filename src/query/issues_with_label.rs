@@ -39,6 +39,7 @@ impl GitHubQuery<'_> {
                     number: issue.number as u32,
                     title: issue.title,
                     open: matches!(issue.state, IssueState::OPEN),
+                    pull_request: None,
                 }
             }));
             let page_info = issues.page_info;
@@ -0,0 +1,180 @@
+use self::query::QueryRepositoryIssueTimelineItemsNodes as TimelineItem;
+use self::query::QueryRepositoryIssueTimelineItemsNodesOnCrossReferencedEventSource as Source;
+use self::query::{PullRequestState, ResponseData, Variables};
+use super::{GitHubQuery, Repo};
+use crate::data::{IssueId, LinkedPr, PrState};
+use anyhow::Context;
+use chrono::Utc;
+use graphql_client::GraphQLQuery;
+use log::info;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/query/linked_prs.graphql",
+    response_derives = "Debug"
+)]
+struct Query;
+
+type DateTime = chrono::DateTime<Utc>;
+
+impl GitHubQuery<'_> {
+    /// Get pull requests which mention the given issue, from cross-reference
+    /// events in its timeline, which are the only events requested.
+    pub async fn query_linked_prs(
+        &self,
+        repo: &Repo,
+        number: IssueId,
+    ) -> anyhow::Result<Vec<LinkedPr>> {
+        info!("fetching pull requests linked to {}#{}...", repo, number);
+        let mut result: Vec<LinkedPr> = Vec::new();
+        let mut cursor = None;
+        loop {
+            let query = Query::build_query(Variables {
+                owner: repo.owner.clone(),
+                name: repo.name.clone(),
+                number: i64::from(number),
+                cursor,
+            });
+            let data: ResponseData = self.send_query("linked_prs", &query).await?;
+            let repository = data.repository.unwrap();
+            let timeline = repository
+                .issue
+                .with_context(|| format!("{}#{} is not an issue", repo, number))?
+                .timeline_items;
+            add_linked_prs(&mut result, timeline.nodes.unwrap().into_iter().flatten());
+            let page_info = timeline.page_info;
+            if page_info.has_next_page {
+                cursor = page_info.end_cursor;
+            } else {
+                break;
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Add pull requests cross-referenced by the given timeline items to the
+/// result, skipping other items and pull requests already in it.
+fn add_linked_prs(result: &mut Vec<LinkedPr>, items: impl IntoIterator<Item = TimelineItem>) {
+    for item in items {
+        let pr = match item {
+            TimelineItem::CrossReferencedEvent(event) => match event.source {
+                Source::PullRequest(pr) => pr,
+                _ => continue,
+            },
+            _ => continue,
+        };
+        let pr_repo = Repo::new(&pr.repository.owner.login, &pr.repository.name);
+        let number = pr.number as u32;
+        // A pull request can mention the issue several times.
+        if result
+            .iter()
+            .any(|linked| linked.repo == pr_repo && linked.number == number)
+        {
+            continue;
+        }
+        result.push(LinkedPr {
+            repo: pr_repo,
+            number,
+            title: pr.title,
+            state: match pr.state {
+                PullRequestState::OPEN => PrState::Open,
+                PullRequestState::MERGED => PrState::Merged,
+                _ => PrState::Closed,
+            },
+            merged_at: pr.merged_at,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::{json, Value};
+
+    fn pr(owner: &str, number: u32, state: &str, merged_at: Option<&str>) -> Value {
+        json!({
+            "__typename": "CrossReferencedEvent",
+            "source": {
+                "__typename": "PullRequest",
+                "number": number,
+                "title": format!("PR {}", number),
+                "state": state,
+                "mergedAt": merged_at,
+                "repository": {
+                    "name": "rust",
+                    "owner": { "__typename": "Organization", "login": owner },
+                },
+            },
+        })
+    }
+
+    fn issue() -> Value {
+        json!({
+            "__typename": "CrossReferencedEvent",
+            "source": { "__typename": "Issue" },
+        })
+    }
+
+    fn add(result: &mut Vec<LinkedPr>, nodes: Value) {
+        let nodes: Vec<Option<TimelineItem>> = serde_json::from_value(nodes).unwrap();
+        add_linked_prs(result, nodes.into_iter().flatten());
+    }
+
+    fn numbers(result: &[LinkedPr]) -> Vec<(&str, IssueId)> {
+        result
+            .iter()
+            .map(|pr| (pr.repo.owner.as_str(), pr.number))
+            .collect()
+    }
+
+    #[test]
+    fn filters_pull_requests() {
+        let mut result = Vec::new();
+        add(
+            &mut result,
+            json!([
+                pr("rust-lang", 1, "OPEN", None),
+                issue(),
+                null,
+                pr("rust-lang", 3, "MERGED", Some("2024-01-02T03:04:05Z")),
+                pr("rust-lang", 4, "CLOSED", None),
+            ]),
+        );
+        assert_eq!(
+            numbers(&result),
+            [("rust-lang", 1), ("rust-lang", 3), ("rust-lang", 4)]
+        );
+        let states: Vec<_> = result.iter().map(|pr| pr.state).collect();
+        assert_eq!(states, [PrState::Open, PrState::Merged, PrState::Closed]);
+        assert_eq!(result[0].merged_at, None);
+        assert_eq!(
+            result[1].merged_at,
+            Some(Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap())
+        );
+        assert_eq!(result[1].title, "PR 3");
+    }
+
+    #[test]
+    fn dedups_across_pages() {
+        let mut result = Vec::new();
+        add(
+            &mut result,
+            json!([
+                pr("rust-lang", 1, "OPEN", None),
+                pr("rust-lang", 1, "OPEN", None)
+            ]),
+        );
+        // The same number in another repository is another pull request.
+        add(
+            &mut result,
+            json!([
+                pr("rust-lang", 1, "OPEN", None),
+                pr("tokio-rs", 1, "OPEN", None)
+            ]),
+        );
+        assert_eq!(numbers(&result), [("rust-lang", 1), ("tokio-rs", 1)]);
+    }
+}
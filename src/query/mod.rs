@@ -9,8 +9,14 @@ use reqwest::header::USER_AGENT;
 mod issue_or_pr;
 mod issues_with_label;
 mod latest_tag;
+mod linked_prs;
+mod search_tracking_issues;
+mod tracking_issue;
+mod tree_entries;
 
-#[derive(Clone, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub use self::tracking_issue::TrackingIssue;
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 pub struct Repo {
     pub owner: String,
     pub name: String,
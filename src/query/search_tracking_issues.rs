@@ -0,0 +1,70 @@
+use self::query::QuerySearchNodes as Node;
+use self::query::{ResponseData, Variables};
+use super::tracking_issue::TrackingIssue;
+use super::{GitHubQuery, Repo};
+use chrono::Utc;
+use graphql_client::GraphQLQuery;
+use log::info;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/query/search_tracking_issues.graphql",
+    response_derives = "Debug"
+)]
+struct Query;
+
+type DateTime = chrono::DateTime<Utc>;
+
+impl GitHubQuery<'_> {
+    /// Search open issues labeled as tracking issues with the given label.
+    pub async fn query_tracking_issues(
+        &self,
+        repo: &Repo,
+        label: &str,
+    ) -> anyhow::Result<Vec<TrackingIssue>> {
+        info!(
+            "searching tracking issues of label {} in {}...",
+            label, repo
+        );
+        let search = format!(
+            "repo:{} is:issue is:open label:C-tracking-issue label:\"{}\"",
+            repo, label
+        );
+        let mut result = Vec::new();
+        let mut cursor = None;
+        loop {
+            let query = Query::build_query(Variables {
+                query: search.clone(),
+                cursor,
+            });
+            let data: ResponseData = self.send_query("search_tracking_issues", &query).await?;
+            let nodes = data.search.nodes.unwrap();
+            result.extend(nodes.into_iter().flatten().filter_map(|node| match node {
+                Node::Issue(issue) => {
+                    let labels = issue.labels.and_then(|labels| labels.nodes);
+                    Some(TrackingIssue {
+                        number: issue.number as u32,
+                        title: issue.title,
+                        body: issue.body,
+                        created_at: issue.created_at,
+                        labels: labels
+                            .into_iter()
+                            .flatten()
+                            .flatten()
+                            .map(|label| label.name)
+                            .collect(),
+                    })
+                }
+                _ => None,
+            }));
+            let page_info = data.search.page_info;
+            if page_info.has_next_page {
+                cursor = page_info.end_cursor;
+            } else {
+                break;
+            }
+        }
+        Ok(result)
+    }
+}
@@ -0,0 +1,59 @@
+use self::query::{ResponseData, Variables};
+use super::{GitHubQuery, Repo};
+use crate::data::IssueId;
+use anyhow::Context;
+use chrono::Utc;
+use graphql_client::GraphQLQuery;
+use log::info;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/query/tracking_issue.graphql",
+    response_derives = "Debug"
+)]
+struct Query;
+
+type DateTime = chrono::DateTime<Utc>;
+
+/// Details of a tracking issue beyond what is cached.
+pub struct TrackingIssue {
+    pub number: IssueId,
+    pub title: String,
+    pub body: String,
+    pub created_at: DateTime,
+    pub labels: Vec<String>,
+}
+
+impl GitHubQuery<'_> {
+    pub async fn query_tracking_issue(
+        &self,
+        repo: &Repo,
+        number: IssueId,
+    ) -> anyhow::Result<TrackingIssue> {
+        info!("fetching tracking issue {}#{}...", repo, number);
+        let query = Query::build_query(Variables {
+            owner: repo.owner.clone(),
+            name: repo.name.clone(),
+            number: i64::from(number),
+        });
+        let data: ResponseData = self.send_query("tracking_issue", &query).await?;
+        let repository = data.repository.unwrap();
+        let issue = repository
+            .issue
+            .with_context(|| format!("{}#{} is not an issue", repo, number))?;
+        let labels = issue.labels.and_then(|labels| labels.nodes);
+        Ok(TrackingIssue {
+            number,
+            title: issue.title,
+            body: issue.body,
+            created_at: issue.created_at,
+            labels: labels
+                .into_iter()
+                .flatten()
+                .flatten()
+                .map(|label| label.name)
+                .collect(),
+        })
+    }
+}
@@ -0,0 +1,41 @@
+use self::query::QueryRepositoryObject as Object;
+use self::query::{ResponseData, Variables};
+use super::{GitHubQuery, Repo};
+use anyhow::bail;
+use graphql_client::GraphQLQuery;
+use log::info;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/query/tree_entries.graphql",
+    response_derives = "Debug"
+)]
+struct Query;
+
+impl GitHubQuery<'_> {
+    /// Get names of entries in a directory given as `<rev>:<path>`.
+    pub async fn query_tree_entries(
+        &self,
+        repo: &Repo,
+        expression: &str,
+    ) -> anyhow::Result<Vec<String>> {
+        info!("listing {} in {}...", expression, repo);
+        let query = Query::build_query(Variables {
+            owner: repo.owner.clone(),
+            name: repo.name.clone(),
+            expression: expression.to_string(),
+        });
+        let data: ResponseData = self.send_query("tree_entries", &query).await?;
+        let repository = data.repository.unwrap();
+        match repository.object {
+            Some(Object::Tree(tree)) => Ok(tree
+                .entries
+                .unwrap_or_default()
+                .into_iter()
+                .map(|entry| entry.name)
+                .collect()),
+            _ => bail!("{} in {} is not a directory", expression, repo),
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! Status report in Markdown or plain text, for pasting into
+//! This Week in Rust drafts, team docs, and GitHub comments.
+
+use crate::data::output::{Item, Stabilization, VersionState};
+use crate::page_gen::{item_path, SITE_URL};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum Format {
+    Markdown,
+    Text,
+}
+
+pub fn render(format: Format, sections: &BTreeMap<String, Vec<Item>>) -> String {
+    let mut result = String::new();
+    for (section, items) in sections.iter() {
+        match format {
+            Format::Markdown => write_markdown_section(&mut result, section, items),
+            Format::Text => write_text_section(&mut result, section, items),
+        }
+    }
+    result
+}
+
+fn write_markdown_section(out: &mut String, section: &str, items: &[Item]) {
+    writeln!(out, "## {}\n", section_title(section)).unwrap();
+    write_markdown_items(out, items, 0);
+    out.push('\n');
+}
+
+fn write_markdown_items(out: &mut String, items: &[Item], depth: usize) {
+    for item in items.iter().rev() {
+        let indent = "  ".repeat(depth);
+        let url = format!("{}{}", SITE_URL, item_path(&item.id));
+        write!(
+            out,
+            "{}- [{}]({}) {}",
+            indent,
+            item.title,
+            url,
+            markdown_badge(item)
+        )
+        .unwrap();
+        if let Some(rfc) = &item.rfc {
+            write!(out, " · [RFC {}]({})", rfc.issue.number, rfc.url).unwrap();
+        }
+        if let Some(tracking) = &item.tracking {
+            write!(
+                out,
+                " · [#{0}](https://github.com/rust-lang/rust/issues/{0})",
+                tracking.number
+            )
+            .unwrap();
+        }
+        if let Some(label) = &item.issue_label {
+            let (open, closed) = count_issues(item);
+            write!(
+                out,
+                " · {} open / {} closed under `{}`",
+                open, closed, label
+            )
+            .unwrap();
+        }
+        out.push('\n');
+        write_markdown_items(out, &item.deps, depth + 1);
+    }
+}
+
+fn markdown_badge(item: &Item) -> String {
+    if item.unresolved.is_some() {
+        return "🔴 **unresolved**".to_string();
+    }
+    if let Some(link) = &item.link {
+        return format!("[{}]({})", link.text, link.url);
+    }
+    match &item.stabilized {
+        Some(stabilized) => {
+            let icon = match stabilized.state {
+                VersionState::Stable => "🟢",
+                VersionState::Beta => "🟡",
+                VersionState::Nightly => "🟣",
+            };
+            let pr = stabilized.pr.number;
+            format!(
+                "{} **{}** ([#{}](https://github.com/rust-lang/rust/pull/{}))",
+                icon,
+                describe_stabilization(stabilized),
+                pr,
+                pr
+            )
+        }
+        None => "⚪ **not stabilized yet**".to_string(),
+    }
+}
+
+fn write_text_section(out: &mut String, section: &str, items: &[Item]) {
+    let stabilized = items.iter().filter(|i| i.stabilized.is_some()).count();
+    writeln!(
+        out,
+        "{} ({}/{} stabilized)",
+        section_title(section),
+        stabilized,
+        items.len()
+    )
+    .unwrap();
+    write_text_items(out, items, 1);
+    out.push('\n');
+}
+
+fn write_text_items(out: &mut String, items: &[Item], depth: usize) {
+    for item in items.iter().rev() {
+        let state = if item.unresolved.is_some() {
+            "unresolved".to_string()
+        } else if item.link.is_some() {
+            "workaround".to_string()
+        } else {
+            item.stabilized
+                .as_ref()
+                .map_or("not stabilized".to_string(), describe_stabilization)
+        };
+        write!(out, "{}[{}] {}", "  ".repeat(depth), state, item.title).unwrap();
+        if item.issue_label.is_some() {
+            let (open, closed) = count_issues(item);
+            write!(out, " ({} open, {} closed issues)", open, closed).unwrap();
+        }
+        out.push('\n');
+        write_text_items(out, &item.deps, depth + 1);
+    }
+}
+
+fn describe_stabilization(stabilized: &Stabilization) -> String {
+    format!("{} {}", stabilized.state.name(), stabilized.version)
+}
+
+fn count_issues(item: &Item) -> (usize, usize) {
+    let open = item.issues.iter().filter(|issue| issue.open).count();
+    (open, item.issues.len() - open)
+}
+
+/// Turn section keys like `async_blockers` into `Async blockers`.
+fn section_title(section: &str) -> String {
+    let title = section.replace('_', " ");
+    let mut chars = title.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => title,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::output::{Rfc, Stabilization};
+    use crate::data::Issue;
+
+    fn issue(number: u32, open: bool) -> Issue {
+        Issue {
+            number,
+            title: format!("Issue {}", number),
+            open,
+            pull_request: None,
+        }
+    }
+
+    fn sections() -> BTreeMap<String, Vec<Item>> {
+        let mut stabilized = Item::new_for_test("async-fn", "Async fn");
+        stabilized.rfc = Some(Rfc {
+            issue: issue(2394, false),
+            url: "https://rust-lang.github.io/rfcs/2394-async_await.html".to_string(),
+            merged: true,
+        });
+        stabilized.tracking = Some(issue(50547, false));
+        stabilized.stabilized = Some(Stabilization {
+            state: VersionState::Stable,
+            version: "1.39".to_string(),
+            release_date: None,
+            pr: issue(63209, false),
+        });
+        let mut dep = Item::new_for_test("diagnostics", "Diagnostics");
+        dep.issue_label = Some("A-async-await".to_string());
+        dep.issues = vec![issue(1, true), issue(2, false), issue(3, true)];
+        stabilized.deps.push(dep);
+        let pending = Item::new_for_test("async-closure", "Async closure");
+        let mut sections = BTreeMap::new();
+        sections.insert("async_basics".to_string(), vec![stabilized, pending]);
+        sections
+    }
+
+    #[test]
+    fn markdown() {
+        let expected = "\
+## Async basics
+
+- [Async closure](https://areweasyncyet.rs/items/async-closure/) ⚪ **not stabilized yet**
+- [Async fn](https://areweasyncyet.rs/items/async-fn/) 🟢 **stable 1.39** \
+([#63209](https://github.com/rust-lang/rust/pull/63209)) \
+· [RFC 2394](https://rust-lang.github.io/rfcs/2394-async_await.html) \
+· [#50547](https://github.com/rust-lang/rust/issues/50547)
+  - [Diagnostics](https://areweasyncyet.rs/items/diagnostics/) ⚪ **not stabilized yet** \
+· 2 open / 1 closed under `A-async-await`
+
+";
+        assert_eq!(render(Format::Markdown, &sections()), expected);
+    }
+
+    #[test]
+    fn markdown_unresolved_and_link() {
+        let mut unresolved = Item::new_for_test("a", "A");
+        unresolved.unresolved = Some(Rfc {
+            issue: issue(1, true),
+            url: "https://github.com/rust-lang/rfcs/pull/1".to_string(),
+            merged: false,
+        });
+        let mut linked = Item::new_for_test("b", "B");
+        linked.link = Some(crate::data::Link {
+            text: "crate".to_string(),
+            url: "https://crates.io/".to_string(),
+        });
+        let mut sections = BTreeMap::new();
+        sections.insert("x".to_string(), vec![unresolved, linked]);
+        let expected = "\
+## X
+
+- [B](https://areweasyncyet.rs/items/b/) [crate](https://crates.io/)
+- [A](https://areweasyncyet.rs/items/a/) 🔴 **unresolved**
+
+";
+        assert_eq!(render(Format::Markdown, &sections), expected);
+    }
+
+    #[test]
+    fn text() {
+        let expected = "\
+Async basics (1/2 stabilized)
+  [not stabilized] Async closure
+  [stable 1.39] Async fn
+    [not stabilized] Diagnostics (2 open, 1 closed issues)
+
+";
+        assert_eq!(render(Format::Text, &sections()), expected);
+    }
+
+    #[test]
+    fn section_titles() {
+        assert_eq!(section_title("async_blockers"), "Async blockers");
+        assert_eq!(section_title(""), "");
+    }
+}
@@ -0,0 +1,159 @@
+use crate::data::input::Item;
+use crate::posts::Post;
+use anyhow::{anyhow, Context, Result};
+use jsonschema::JSONSchema;
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::path::Path;
+
+pub const DATA_SCHEMA_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/schema/data.schema.json");
+pub const POSTS_SCHEMA_FILE: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/schema/posts.schema.json");
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum SchemaKind {
+    Data,
+    Posts,
+}
+
+impl SchemaKind {
+    pub fn schema(self) -> RootSchema {
+        let (mut schema, title) = match self {
+            SchemaKind::Data => (schema_for!(BTreeMap<String, Vec<Item>>), "data.yml"),
+            SchemaKind::Posts => (schema_for!(Vec<Post>), "posts.yml"),
+        };
+        schema.schema.metadata().title = Some(title.to_string());
+        schema
+    }
+
+    pub fn schema_file(self) -> &'static str {
+        match self {
+            SchemaKind::Data => DATA_SCHEMA_FILE,
+            SchemaKind::Posts => POSTS_SCHEMA_FILE,
+        }
+    }
+
+    pub fn to_json(self) -> Result<String> {
+        let mut json = serde_json::to_string_pretty(&self.schema())?;
+        json.push('\n');
+        Ok(json)
+    }
+}
+
+/// Check that the schema file in the repository is up to date with
+/// the one derived from the types.
+pub fn check_schema_file(kind: SchemaKind) -> Result<()> {
+    let path = kind.schema_file();
+    let content = fs::read_to_string(path).with_context(|| format!("failed to read {}", path))?;
+    if content != kind.to_json()? {
+        return Err(anyhow!(
+            "{} is outdated, regenerate it with `cargo run -- schema --write`",
+            path
+        ));
+    }
+    Ok(())
+}
+
+/// Validate the given yaml file against the schema.
+pub fn validate_file(kind: SchemaKind, path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    let file = File::open(path)?;
+    let instance: Value = serde_yaml::from_reader(file).context("failed to read yaml")?;
+    let schema = serde_json::to_value(kind.schema())?;
+    let compiled = JSONSchema::compile(&schema).map_err(|e| anyhow!("invalid schema: {}", e))?;
+    if let Err(errors) = compiled.validate(&instance) {
+        let messages: Vec<_> = errors
+            .map(|e| format!("  {}: {}", e.instance_path, e))
+            .collect();
+        return Err(anyhow!(
+            "{} doesn't match the schema:\n{}",
+            path.display(),
+            messages.join("\n")
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DATA_FILE, POSTS_FILE};
+
+    fn validate_str(kind: SchemaKind, content: &str) -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.yml");
+        fs::write(&path, content).unwrap();
+        validate_file(kind, path)
+    }
+
+    #[test]
+    fn schema_files_are_up_to_date() {
+        check_schema_file(SchemaKind::Data).unwrap();
+        check_schema_file(SchemaKind::Posts).unwrap();
+    }
+
+    #[test]
+    fn repository_files_are_valid() {
+        validate_file(SchemaKind::Data, DATA_FILE).unwrap();
+        validate_file(SchemaKind::Posts, POSTS_FILE).unwrap();
+    }
+
+    #[test]
+    fn valid_data() {
+        let content = r#"
+a:
+  - title: A
+    rfc: 2394-async_await
+    tracking: 50547
+    stabilized:
+      version: "1.39"
+      pr: 63209
+    deps:
+      - title: B
+        id: b
+        draft: true
+  - title: C
+    rfc: 2362
+    unresolved: 2394-async_await#unresolved-questions
+"#;
+        validate_str(SchemaKind::Data, content).unwrap();
+    }
+
+    #[test]
+    fn invalid_data() {
+        let cases = [
+            // Missing title.
+            "a:\n  - id: a\n",
+            // Unknown field.
+            "a:\n  - title: A\n    tracked: 1\n",
+            // Invalid id.
+            "a:\n  - title: A\n    id: Not An Id\n",
+            // RFC without a number.
+            "a:\n  - title: A\n    rfc: async_await\n",
+            // Negative RFC number.
+            "a:\n  - title: A\n    rfc: -1\n",
+            // Version without minor part.
+            "a:\n  - title: A\n    stabilized:\n      version: \"1\"\n      pr: 1\n",
+            // Invalid field of a dep.
+            "a:\n  - title: A\n    deps:\n      - title: B\n        tracking: x\n",
+        ];
+        for content in cases {
+            let err = validate_str(SchemaKind::Data, content).unwrap_err();
+            assert!(
+                err.to_string().contains("doesn't match the schema"),
+                "{}: {}",
+                content,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_posts() {
+        let content = "- title: A\n  url: https://example.com/\n";
+        assert!(validate_str(SchemaKind::Posts, content).is_err());
+    }
+}
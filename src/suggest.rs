@@ -0,0 +1,308 @@
+//! Suggestions of data.yml updates from the fetched state on GitHub.
+
+use crate::cache::Cache;
+use crate::data::edit::{self, Document};
+use crate::data::input::{InputData, Item};
+use crate::data::{Issue, IssueId};
+use crate::fetcher::{self, IssueData};
+use crate::query::{GitHubQuery, Repo};
+use crate::{DATA_FILE, RFC_REPO, RUSTC_REPO};
+use anyhow::{Context, Result};
+use similar::TextDiff;
+use std::fs;
+
+/// Directory of merged RFCs in the RFC repository.
+const RFC_TEXT_DIR: &str = "master:text";
+
+enum Suggestion {
+    /// Set the field at the path of the item to the value.
+    Set {
+        item: String,
+        path: &'static [&'static str],
+        value: String,
+    },
+    /// Add a comment above the item for things that need a human decision.
+    Comment { item: String, text: String },
+}
+
+pub async fn run(query: &GitHubQuery<'_>) -> Result<()> {
+    let text = fs::read_to_string(DATA_FILE).context("failed to read data.yml")?;
+    let input_data = InputData::from_file(DATA_FILE).context("failed to read input data")?;
+
+    let mut cache = Cache::open()?;
+    let mut issue_data = cache.load_or_default()?;
+    forget_referenced_issues(&mut issue_data, &input_data);
+    issue_data
+        .fetch_data(
+            query,
+            &input_data.get_fetch_list(),
+            fetcher::max_age_from_env()?,
+        )
+        .await?;
+    cache.store(&issue_data)?;
+    drop(cache);
+
+    let mut suggestions = Vec::new();
+    let mut rfc_files = None;
+    for item in input_data.all_items() {
+        let id = item.id();
+        suggestions.extend(check_item(item, &issue_data)?);
+        let number = match item.rfc.as_deref().map(str::parse::<IssueId>) {
+            Some(Ok(number)) => number,
+            _ => continue,
+        };
+        let pr = get_issue(&issue_data, &RFC_REPO, number)?;
+        match pr.pull_request.as_ref() {
+            Some(info) if info.merged => {
+                if rfc_files.is_none() {
+                    let files = query.query_tree_entries(&RFC_REPO, RFC_TEXT_DIR).await?;
+                    rfc_files = Some(files);
+                }
+                let prefix = format!("{:04}-", number);
+                let file = rfc_files
+                    .as_ref()
+                    .unwrap()
+                    .iter()
+                    .find(|file| file.starts_with(&prefix) && file.ends_with(".md"));
+                match file {
+                    Some(file) => suggestions.push(Suggestion::Set {
+                        item: id,
+                        path: &["rfc"],
+                        value: edit::quote(file.trim_end_matches(".md")),
+                    }),
+                    None => suggestions.push(Suggestion::Comment {
+                        item: id,
+                        text: format!(
+                            "RFC #{} has been merged, but its text was not found",
+                            number
+                        ),
+                    }),
+                }
+            }
+            Some(_) if !pr.open => suggestions.push(Suggestion::Comment {
+                item: id,
+                text: format!("RFC #{} has been closed without merging", number),
+            }),
+            _ => {}
+        }
+    }
+
+    let new_text = apply(&text, &suggestions)?;
+    if new_text == text {
+        eprintln!("data.yml is up to date");
+        return Ok(());
+    }
+    print!("{}", unified_diff(&text, &new_text));
+    Ok(())
+}
+
+/// Apply the given suggestions to the text of data.yml.
+fn apply(text: &str, suggestions: &[Suggestion]) -> Result<String> {
+    let mut document = Document::new(text);
+    for suggestion in suggestions.iter() {
+        match suggestion {
+            Suggestion::Set { item, path, value } => {
+                let span = document.find_item(item)?;
+                document.set_field(span, path, value)?;
+            }
+            Suggestion::Comment { item, text } => {
+                let span = document.find_item(item)?;
+                document.insert_comment(span, &format!("TODO: {}", text));
+            }
+        }
+    }
+    Ok(document.text())
+}
+
+fn unified_diff(old: &str, new: &str) -> String {
+    let diff = TextDiff::from_lines(old, new);
+    diff.unified_diff()
+        .header("a/data.yml", "b/data.yml")
+        .to_string()
+}
+
+/// Suggestions for the item which only depend on fetched issues.
+fn check_item(item: &Item, issue_data: &IssueData) -> Result<Vec<Suggestion>> {
+    let mut suggestions = Vec::new();
+    if let Some(text) = check_tracking(item, issue_data)? {
+        suggestions.push(Suggestion::Comment {
+            item: item.id(),
+            text,
+        });
+    }
+    if let Some(version) = check_stabilization(item, issue_data)? {
+        suggestions.push(Suggestion::Set {
+            item: item.id(),
+            path: &["stabilized", "version"],
+            value: edit::quote(&version),
+        });
+    }
+    Ok(suggestions)
+}
+
+/// A tracking issue closed while the item is not stabilized.
+fn check_tracking(item: &Item, issue_data: &IssueData) -> Result<Option<String>> {
+    let tracking = match item.tracking {
+        Some(tracking) if item.stabilized.is_none() => tracking,
+        _ => return Ok(None),
+    };
+    if get_issue(issue_data, &RUSTC_REPO, tracking)?.open {
+        return Ok(None);
+    }
+    Ok(Some(format!(
+        "tracking issue #{} has been closed, add `stabilized` if it was stabilized",
+        tracking
+    )))
+}
+
+/// Version of a merged stabilization pull request without a version.
+fn check_stabilization(item: &Item, issue_data: &IssueData) -> Result<Option<String>> {
+    let stabilized = match &item.stabilized {
+        Some(stabilized) if stabilized.version.is_none() => stabilized,
+        _ => return Ok(None),
+    };
+    let pr = get_issue(issue_data, &RUSTC_REPO, stabilized.pr)?;
+    // Milestones of rust-lang/rust are named after the full version.
+    let milestone = pr
+        .pull_request
+        .as_ref()
+        .filter(|info| info.merged)
+        .and_then(|info| info.milestone.as_deref());
+    let mut parts = match milestone {
+        Some(milestone) => milestone.split('.'),
+        None => return Ok(None),
+    };
+    match (parts.next(), parts.next()) {
+        (Some(major), Some(minor)) => Ok(Some(format!("{}.{}", major, minor))),
+        _ => Ok(None),
+    }
+}
+
+/// Remove cached tracking issues, stabilization pull requests and RFC pull
+/// requests referenced by data.yml, so that they get fetched again and
+/// suggestions are made from their current state rather than a cached one.
+fn forget_referenced_issues(issue_data: &mut IssueData, input_data: &InputData) {
+    for item in input_data.all_items() {
+        let mut keys = Vec::new();
+        if let Some(tracking) = item.tracking {
+            keys.push((RUSTC_REPO.clone(), tracking));
+        }
+        if let Some(stabilized) = &item.stabilized {
+            keys.push((RUSTC_REPO.clone(), stabilized.pr));
+        }
+        if let Some(Ok(number)) = item.rfc.as_deref().map(str::parse) {
+            keys.push((RFC_REPO.clone(), number));
+        }
+        for key in keys {
+            issue_data.issues.remove(&key);
+            issue_data.issues_fetched.remove(&key);
+        }
+    }
+}
+
+fn get_issue<'a>(issue_data: &'a IssueData, repo: &Repo, number: IssueId) -> Result<&'a Issue> {
+    issue_data
+        .issues
+        .get(&(repo.clone(), number))
+        .with_context(|| format!("{}#{} was not fetched", repo, number))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::PullRequest;
+
+    const DATA: &str = "\
+Section:
+  - title: Closed
+    tracking: 1
+  - title: Open
+    tracking: 2
+  - title: Merged
+    tracking: 3
+    stabilized:
+      pr: 4
+";
+
+    fn issue_data() -> IssueData {
+        let mut data = IssueData::default();
+        let issue = |number, open, pull_request| Issue {
+            number,
+            title: format!("Issue {}", number),
+            open,
+            pull_request,
+        };
+        let pr = PullRequest {
+            merged: true,
+            milestone: Some("1.40.0".to_string()),
+        };
+        for issue in [
+            issue(1, false, None),
+            issue(2, true, None),
+            issue(3, false, None),
+            issue(4, false, Some(pr)),
+        ] {
+            data.issues
+                .insert((RUSTC_REPO.clone(), issue.number), issue);
+        }
+        data
+    }
+
+    fn suggest(data: &str, issue_data: &IssueData) -> Result<String> {
+        let input_data = InputData::parse(data)?;
+        let mut suggestions = Vec::new();
+        for item in input_data.all_items() {
+            suggestions.extend(check_item(item, issue_data)?);
+        }
+        Ok(unified_diff(data, &apply(data, &suggestions)?))
+    }
+
+    #[test]
+    fn closed_tracking_issue() {
+        let diff = suggest(DATA, &issue_data()).unwrap();
+        assert_eq!(
+            diff,
+            "\
+--- a/data.yml
++++ b/data.yml
+@@ -1,4 +1,5 @@
+ Section:
++  # TODO: tracking issue #1 has been closed, add `stabilized` if it was stabilized
+   - title: Closed
+     tracking: 1
+   - title: Open
+@@ -6,4 +7,5 @@
+   - title: Merged
+     tracking: 3
+     stabilized:
++      version: \"1.40\"
+       pr: 4
+"
+        );
+    }
+
+    #[test]
+    fn missing_issue() {
+        let mut issue_data = issue_data();
+        issue_data.issues.remove(&(RUSTC_REPO.clone(), 1));
+        let error = suggest(DATA, &issue_data).unwrap_err();
+        assert!(
+            error.to_string().contains("#1 was not fetched"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn forget_referenced() {
+        let mut issue_data = issue_data();
+        let other = (RUSTC_REPO.clone(), 5);
+        for key in [(RUSTC_REPO.clone(), 1), other.clone()] {
+            issue_data.issues_fetched.insert(key, Default::default());
+        }
+        forget_referenced_issues(&mut issue_data, &InputData::parse(DATA).unwrap());
+        assert!(issue_data.issues.is_empty());
+        let fetched: Vec<_> = issue_data.issues_fetched.keys().collect();
+        assert_eq!(fetched, [&other]);
+    }
+}